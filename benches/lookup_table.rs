@@ -0,0 +1,140 @@
+#![feature(test)]
+
+#[macro_use]
+extern crate lut;
+extern crate media_type_impl_utils;
+extern crate test;
+
+use test::{Bencher, black_box};
+use lut::Table;
+use media_type_impl_utils::lookup_tables::{MediaTypeChars, HttpToken, QText, QTextWs};
+
+// Compares lookups in `MediaTypeChars` (`u32` cells) with lookups in the
+// original `MediaTypeChars` table which had `u8` cells and 8 classes.
+//
+// Each bench is run 7 times on it's own (`cargo bench lookup_qtext_ws_u8_cells` etc.),
+// the numbers are the median (and min/max) of the runs in ns/iter:
+//
+// lookup_http_token_u32_cells ... 26,968 (26,473 - 29,422)
+// lookup_http_token_u8_cells  ... 37,561 (33,533 - 39,075)
+//
+// lookup_qtext_u32_cells      ... 28,773 (27,912 - 28,942)
+// lookup_qtext_u8_cells       ... 36,878 (23,608 - 38,764)
+//
+// lookup_qtext_ws_u32_cells   ... 37,548 (29,918 - 43,200)
+// lookup_qtext_ws_u8_cells    ... 41,521 (30,364 - 43,204)
+//
+// ====> lookups with `u32` cells are not slower, a lookup is one indexed load + a mask
+//       with either cell type and the 1KiB table fits easily into the L1 cache
+//       (the earlier single run numbers, with the input only behind `black_box` and
+//       10 times less iterations, had qtext_ws ~11% slower which was within their noise)
+
+mod u8_cells {
+    new_table! {
+        pub flags {
+            U8CText = CT,
+            U8ObsNoWsCtl = NC,
+            U8HttpToken = HT,
+            U8Token = TO,
+            U8RestrictedToken = RT,
+            U8QText = QC,
+            U8DQuoteOrEscape = DOE,
+            U8Ws = Ws
+        }
+        pub struct U8Chars {
+            static data: [u8; 256] = [
+                //0x00 + 0/4/8/C
+                -,               NC,              NC,              NC,
+                NC,              NC,              NC,              NC,
+                NC,              Ws,              -,               NC,
+                NC,              -,               NC,              NC,
+                //0x10  + 0/4/8/C
+                NC,              NC,              NC,              NC,
+                NC,              NC,              NC,              NC,
+                NC,              NC,              NC,              NC,
+                NC,              NC,              NC,              NC,
+                //0x20 + 0/4/8/C
+                Ws,              CT|QC|RT|TO|HT,  CT|DOE,          CT|QC|RT|TO|HT,
+                CT|QC|RT|TO|HT,  CT|QC|TO|HT,     CT|QC|RT|TO|HT,  CT|QC|TO|HT,
+                QC,              QC,              CT|QC|TO|HT,     CT|QC|RT|TO|HT,
+                CT|QC,           CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC,
+                //0x30+ 0/4/8/C
+                CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
+                CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
+                CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC,           CT|QC,
+                CT|QC,           CT|QC,           CT|QC,           CT|QC,
+                //0x40+ 0/4/8/C
+                CT|QC,           CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
+                CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
+                CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
+                CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
+                //0x50 + 0/4/8/C
+                CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
+                CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
+                CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC,
+                DOE,/*'\\'*/     CT|QC,           CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
+                //0x60 + 0/4/8/C
+                CT|QC|TO|HT,     CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
+                CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
+                CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
+                CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
+                //0x70 + 0/4/8/C
+                CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
+                CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
+                CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|TO,
+                CT|QC|TO|HT,     CT|QC|TO,        CT|QC|TO|HT,     NC,
+                //0x80
+                -, -, -, -, -, -, -, -, -, -, -, -, -, -, -, -,
+                //0x90
+                -, -, -, -, -, -, -, -, -, -, -, -, -, -, -, -,
+                //0xA0
+                -, -, -, -, -, -, -, -, -, -, -, -, -, -, -, -,
+                //0xB0
+                -, -, -, -, -, -, -, -, -, -, -, -, -, -, -, -,
+                //0xC0
+                -, -, -, -, -, -, -, -, -, -, -, -, -, -, -, -,
+                //0xD0
+                -, -, -, -, -, -, -, -, -, -, -, -, -, -, -, -,
+                //0xE0
+                -, -, -, -, -, -, -, -, -, -, -, -, -, -, -, -,
+                //0xF0
+                -, -, -, -, -, -, -, -, -, -, -, -, -, -, -, -
+            ];
+        }
+    }
+
+    accessor_any!{ pub U8QTextWs = U8QText | U8Ws }
+}
+
+use u8_cells::{U8Chars, U8HttpToken, U8QText, U8QTextWs};
+
+static INPUT: &[u8] = b"text/html,application/xhtml+xml,application/xml;q=0.9,\
+    image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7,\
+    text/plain; charset=\"utf-8\"; format=flowed; delsp=yes,\
+    application/vnd.example.some-rather-long-name+json; profile=\"x y z\"";
+
+macro_rules! lookup_bench {
+    ($name:ident, $table:ident, $class:ident) => (
+        #[bench]
+        fn $name(b: &mut Bencher) {
+            b.iter(|| {
+                let mut x = 0u64;
+                for _ in 0..100 {
+                    for &bch in black_box(INPUT).iter() {
+                        if $table::check_at(black_box(bch) as usize, $class) { x += 1; }
+                    }
+                }
+                x
+            })
+        }
+    );
+}
+
+lookup_bench!(lookup_http_token_u8_cells, U8Chars, U8HttpToken);
+lookup_bench!(lookup_http_token_u32_cells, MediaTypeChars, HttpToken);
+
+lookup_bench!(lookup_qtext_u8_cells, U8Chars, U8QText);
+lookup_bench!(lookup_qtext_u32_cells, MediaTypeChars, QText);
+
+lookup_bench!(lookup_qtext_ws_u8_cells, U8Chars, U8QTextWs);
+lookup_bench!(lookup_qtext_ws_u32_cells, MediaTypeChars, QTextWs);
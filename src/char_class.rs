@@ -0,0 +1,460 @@
+use std::fmt::Write;
+use std::marker::PhantomData;
+use std::ops::RangeInclusive;
+
+use lut::{Table, Access};
+use lookup_tables::MediaTypeChars;
+use qs::spec::{
+    PartialCodePoint,
+    WithoutQuotingValidator,
+    QuotingClassifier, QuotingClass,
+};
+
+/// a 256-entry character class table, one entry for every byte
+///
+/// It can be build from any class (or accessor) of `MediaTypeChars` and
+/// from bytes and combined through `union`, `intersection` and `difference`.
+///
+/// # Example
+///
+/// ```
+/// use media_type_impl_utils::char_class::CharClassTable;
+/// use media_type_impl_utils::lookup_tables::{HttpToken, RestrictedToken};
+///
+/// // http tokens without the chars which are not in restricted token, but with '~'
+/// let table = CharClassTable::from_class(HttpToken)
+///     .intersection(CharClassTable::from_class(RestrictedToken))
+///     .union(CharClassTable::from_bytes(b"~"));
+///
+/// assert!(table.contains(b'a'));
+/// assert!(table.contains(b'~'));
+/// assert!(!table.contains(b'*'));
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct CharClassTable {
+    table: [bool; 256]
+}
+
+impl Default for CharClassTable {
+    fn default() -> Self {
+        CharClassTable::empty()
+    }
+}
+
+impl CharClassTable {
+
+    /// create a table containing no bytes
+    pub fn empty() -> Self {
+        CharClassTable { table: [false; 256] }
+    }
+
+    /// create a table containing all bytes in the given `MediaTypeChars` class
+    ///
+    /// This works with flags (e.g. `HttpToken`), accessors (e.g. `QTextWs`) and
+    /// dynamic accessors (e.g. `Any::new(Token) | Ws`).
+    pub fn from_class<A: Access<MediaTypeChars>>(class: A) -> Self {
+        let mut table = [false; 256];
+        for (idx, entry) in table.iter_mut().enumerate() {
+            *entry = class.check(MediaTypeChars::lookup(idx));
+        }
+        CharClassTable { table }
+    }
+
+    /// create a table containing the given bytes
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut table = [false; 256];
+        for &bch in bytes {
+            table[bch as usize] = true;
+        }
+        CharClassTable { table }
+    }
+
+    /// create a table containing all bytes for which the predicate returns true
+    pub fn from_fn<F: FnMut(u8) -> bool>(mut pred: F) -> Self {
+        let mut table = [false; 256];
+        for (idx, entry) in table.iter_mut().enumerate() {
+            *entry = pred(idx as u8);
+        }
+        CharClassTable { table }
+    }
+
+    /// the union of this and the other table (any byte in either of them)
+    pub fn union(mut self, other: CharClassTable) -> Self {
+        for (entry, other) in self.table.iter_mut().zip(other.table.iter()) {
+            *entry |= *other;
+        }
+        self
+    }
+
+    /// the intersection of this and the other table (any byte in both of them)
+    pub fn intersection(mut self, other: CharClassTable) -> Self {
+        for (entry, other) in self.table.iter_mut().zip(other.table.iter()) {
+            *entry &= *other;
+        }
+        self
+    }
+
+    /// the difference of this and the other table (any byte in this but not in the other one)
+    pub fn difference(mut self, other: CharClassTable) -> Self {
+        for (entry, other) in self.table.iter_mut().zip(other.table.iter()) {
+            *entry &= !*other;
+        }
+        self
+    }
+
+    /// returns true if the byte is in this class
+    #[inline(always)]
+    pub fn contains(&self, byte: u8) -> bool {
+        self.table[byte as usize]
+    }
+
+    /// the number of bytes in this class
+    pub fn len(&self) -> usize {
+        self.table.iter().filter(|&&entry| entry).count()
+    }
+
+    /// returns true if no byte is in this class
+    pub fn is_empty(&self) -> bool {
+        !self.table.iter().any(|&entry| entry)
+    }
+
+    /// all bytes in this class in ascending order
+    pub fn members(&self) -> Vec<u8> {
+        (0..=255u8).filter(|&bch| self.contains(bch)).collect()
+    }
+
+    /// the bytes in this class as compact (maximal) ranges in ascending order
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use media_type_impl_utils::char_class::CharClassTable;
+    /// use media_type_impl_utils::lookup_tables::QTextWs;
+    ///
+    /// let table = CharClassTable::from_class(QTextWs);
+    /// assert_eq!(table.ranges(), vec![
+    ///     0x09..=0x09, 0x20..=0x21, 0x23..=0x5B, 0x5D..=0x7E
+    /// ]);
+    /// ```
+    pub fn ranges(&self) -> Vec<RangeInclusive<u8>> {
+        let mut ranges = Vec::new();
+        let mut start = None;
+        for idx in 0..=256usize {
+            let is_member = idx < 256 && self.table[idx];
+            match (start, is_member) {
+                (None, true) => start = Some(idx as u8),
+                (Some(first), false) => {
+                    ranges.push(first..=(idx - 1) as u8);
+                    start = None;
+                },
+                _ => {}
+            }
+        }
+        ranges
+    }
+
+    /// render this class as ABNF (RFC 5234) alternatives, e.g. `%x09 / %x20-7E`
+    ///
+    /// A empty class is rendered as empty string (ABNF has no empty alternative).
+    pub fn to_abnf(&self) -> String {
+        let mut out = String::new();
+        for (idx, range) in self.ranges().into_iter().enumerate() {
+            if idx > 0 {
+                out.push_str(" / ");
+            }
+            let (first, last) = (*range.start(), *range.end());
+            if first == last {
+                write!(out, "%x{:02X}", first).unwrap();
+            } else {
+                write!(out, "%x{:02X}-{:02X}", first, last).unwrap();
+            }
+        }
+        out
+    }
+
+    /// render this class as regex character class, e.g. `[\x09\x20-\x7E]`
+    ///
+    /// All bytes are written as `\xHH` escapes, which is understood by most regex
+    /// dialects (e.g. PCRE, JavaScript, Python and the `regex` crate, note that
+    /// bytes > 0x7f might be interpreted as code points by some of them).
+    /// A empty class is rendered as `[^\x00-\xFF]`.
+    pub fn to_regex(&self) -> String {
+        if self.is_empty() {
+            return "[^\\x00-\\xFF]".to_owned();
+        }
+        let mut out = String::from("[");
+        for range in self.ranges() {
+            let (first, last) = (*range.start(), *range.end());
+            if first == last {
+                write!(out, "\\x{:02X}", first).unwrap();
+            } else {
+                write!(out, "\\x{:02X}-\\x{:02X}", first, last).unwrap();
+            }
+        }
+        out.push(']');
+        out
+    }
+}
+
+/// A type level character class, normally a zero-sized type defined with `char_class!`.
+///
+/// This is needed to plug custom classes into e.g. `QuotingClassifier` which
+/// works without a instance of the classifier.
+pub trait CharClass {
+    /// the table of the class
+    fn table() -> &'static CharClassTable;
+
+    /// returns true if the byte is in this class
+    #[inline]
+    fn contains(byte: u8) -> bool {
+        Self::table().contains(byte)
+    }
+}
+
+/// Defines a zero-sized type implementing `CharClass` from a `CharClassTable` expression.
+///
+/// The table is build the first time it's used.
+///
+/// # Example
+///
+/// ```
+/// #[macro_use]
+/// extern crate media_type_impl_utils;
+///
+/// use media_type_impl_utils::char_class::{CharClass, CharClassTable};
+/// use media_type_impl_utils::lookup_tables::HttpToken;
+///
+/// char_class! {
+///     /// http token chars except `'.'`
+///     pub struct NoDotToken = CharClassTable::from_class(HttpToken)
+///         .difference(CharClassTable::from_bytes(b"."));
+/// }
+///
+/// fn main() {
+///     assert!(NoDotToken::contains(b'a'));
+///     assert!(!NoDotToken::contains(b'.'));
+/// }
+/// ```
+#[macro_export]
+macro_rules! char_class {
+    ($(#[$attr:meta])* pub struct $name:ident = $table:expr;) => (
+        char_class!{ @IMPL $(#[$attr])* (pub) $name = $table }
+    );
+    ($(#[$attr:meta])* pub($($vis:tt)+) struct $name:ident = $table:expr;) => (
+        char_class!{ @IMPL $(#[$attr])* (pub($($vis)+)) $name = $table }
+    );
+    ($(#[$attr:meta])* struct $name:ident = $table:expr;) => (
+        char_class!{ @IMPL $(#[$attr])* () $name = $table }
+    );
+    (@IMPL $(#[$attr:meta])* ($($vis:tt)*) $name:ident = $table:expr) => (
+        $(#[$attr])*
+        #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+        $($vis)* struct $name;
+
+        impl $crate::char_class::CharClass for $name {
+            fn table() -> &'static $crate::char_class::CharClassTable {
+                static TABLE: ::std::sync::OnceLock<$crate::char_class::CharClassTable> =
+                    ::std::sync::OnceLock::new();
+                TABLE.get_or_init(|| $table)
+            }
+        }
+    );
+}
+
+char_class! {
+    /// the chars which need to be escaped in a quoted-string i.e. `'"'` and `'\\'`
+    pub struct DQuoteOrEscapeClass = CharClassTable::from_bytes(b"\"\\");
+}
+
+/// a type providing a `WithoutQuotingValidator` for tokens consisting of chars of the class `C`
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct ClassTokenValidator<C: CharClass> {
+    class: PhantomData<C>
+}
+
+impl<C> ClassTokenValidator<C>
+    where C: CharClass
+{
+    /// create a new ClassTokenValidator
+    pub fn new() -> Self {
+        ClassTokenValidator { class: PhantomData }
+    }
+}
+
+impl<C> WithoutQuotingValidator for ClassTokenValidator<C>
+    where C: CharClass
+{
+    fn next(&mut self, pcp: PartialCodePoint) -> bool {
+        C::contains(pcp.as_u8())
+    }
+}
+
+/// a type providing a `QuotingClassifier` impl based on character classes
+///
+/// Chars in `Q` are qtext, chars in `N` need quoting and anything else is invalid.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct ClassQuoting<Q: CharClass, N: CharClass = DQuoteOrEscapeClass> {
+    classes: PhantomData<(Q, N)>
+}
+
+impl<Q, N> QuotingClassifier for ClassQuoting<Q, N>
+    where Q: CharClass, N: CharClass
+{
+    fn classify_for_quoting(pcp: PartialCodePoint) -> QuotingClass {
+        let iu8 = pcp.as_u8();
+        if Q::contains(iu8) {
+            QuotingClass::QText
+        } else if N::contains(iu8) {
+            QuotingClass::NeedsQuoting
+        } else {
+            QuotingClass::Invalid
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+    use lut::Any;
+    use qs::{self, spec::{GeneralQSSpec, QuotingClassifier, QuotingClass, PartialCodePoint}};
+    use lookup_tables::{HttpToken, Token, RestrictedToken, QTextWs, Ws, VChar, ObsQText};
+    use quoted_string::NormalParsingImpl;
+    use super::*;
+
+    char_class! {
+        /// http and mime token
+        struct BothTokens = CharClassTable::from_class(HttpToken)
+            .intersection(CharClassTable::from_class(Token));
+    }
+
+    char_class! {
+        /// restricted token without `'+'` and `'.'`
+        struct NoSuffixToken = CharClassTable::from_class(RestrictedToken)
+            .difference(CharClassTable::from_bytes(b"+."));
+    }
+
+    char_class! {
+        /// QText and ws
+        struct MyQText = CharClassTable::from_class(QTextWs);
+    }
+
+    #[derive(Copy, Clone, Debug)]
+    struct MySpec;
+
+    impl GeneralQSSpec for MySpec {
+        type Quoting = ClassQuoting<MyQText>;
+        type Parsing = NormalParsingImpl;
+    }
+
+    #[test]
+    fn from_class_matches_lookup_table() {
+        let table = CharClassTable::from_class(HttpToken);
+        for bch in 0..=255u8 {
+            assert_eq!(table.contains(bch), MediaTypeChars::check_at(bch as usize, HttpToken));
+        }
+    }
+
+    #[test]
+    fn from_class_works_with_dynamic_accessors() {
+        let table = CharClassTable::from_class(Any::new(Token) | Ws);
+        assert!(table.contains(b' '));
+        assert!(table.contains(b'{'));
+        assert!(!table.contains(b'('));
+    }
+
+    #[test]
+    fn set_operations() {
+        let ab = CharClassTable::from_bytes(b"ab");
+        let bc = CharClassTable::from_bytes(b"bc");
+        assert_eq!(ab.union(bc), CharClassTable::from_bytes(b"abc"));
+        assert_eq!(ab.intersection(bc), CharClassTable::from_bytes(b"b"));
+        assert_eq!(ab.difference(bc), CharClassTable::from_bytes(b"a"));
+        assert_eq!(ab.difference(ab), CharClassTable::empty());
+    }
+
+    #[test]
+    fn from_fn() {
+        let table = CharClassTable::from_fn(|bch| bch > 0x7f);
+        assert!(!table.contains(0x7f));
+        assert!(table.contains(0x80));
+        assert!(table.contains(0xff));
+    }
+
+    #[test]
+    fn members_and_len() {
+        let table = CharClassTable::from_class(Ws);
+        assert_eq!(table.members(), vec![b'\t', b' ']);
+        assert_eq!(table.len(), 2);
+        assert!(!table.is_empty());
+        assert!(CharClassTable::empty().is_empty());
+        assert_eq!(CharClassTable::from_class(VChar).len(), 94);
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(CharClassTable::from_class(VChar).ranges(), vec![0x21..=0x7E]);
+        assert_eq!(CharClassTable::empty().ranges(), vec![]);
+        assert_eq!(CharClassTable::from_fn(|_| true).ranges(), vec![0x00..=0xFF]);
+        assert_eq!(CharClassTable::from_bytes(b"\x00\xff").ranges(), vec![0..=0, 0xFF..=0xFF]);
+    }
+
+    #[test]
+    fn ranges_cover_exactly_the_members() {
+        let table = CharClassTable::from_class(ObsQText);
+        let from_ranges = table.ranges().into_iter().flatten().collect::<Vec<_>>();
+        assert_eq!(from_ranges, table.members());
+    }
+
+    #[test]
+    fn to_abnf() {
+        assert_eq!(CharClassTable::from_class(VChar).to_abnf(), "%x21-7E");
+        assert_eq!(CharClassTable::from_class(QTextWs).to_abnf(),
+                   "%x09 / %x20-21 / %x23-5B / %x5D-7E");
+        assert_eq!(CharClassTable::from_class(ObsQText).to_abnf(),
+                   "%x01-08 / %x0B-0C / %x0E-1F / %x21 / %x23-5B / %x5D-7F");
+        assert_eq!(CharClassTable::empty().to_abnf(), "");
+    }
+
+    #[test]
+    fn to_regex() {
+        assert_eq!(CharClassTable::from_class(VChar).to_regex(), "[\\x21-\\x7E]");
+        assert_eq!(CharClassTable::from_class(QTextWs).to_regex(),
+                   "[\\x09\\x20-\\x21\\x23-\\x5B\\x5D-\\x7E]");
+        assert_eq!(CharClassTable::empty().to_regex(), "[^\\x00-\\xFF]");
+    }
+
+    #[test]
+    fn intersection_of_classes() {
+        // '{' is only a mime token, '^' is in both
+        assert!(!BothTokens::contains(b'{'));
+        assert!(BothTokens::contains(b'^'));
+    }
+
+    #[test]
+    fn token_validator_with_custom_class() {
+        let mut vali = ClassTokenValidator::<NoSuffixToken>::new();
+        let res = qs::quote_if_needed::<MySpec, _>("vnd-abc", &mut vali).unwrap();
+        assert_eq!(res, Cow::Borrowed("vnd-abc"));
+
+        let mut vali = ClassTokenValidator::<NoSuffixToken>::new();
+        let res = qs::quote_if_needed::<MySpec, _>("vnd.abc", &mut vali).unwrap();
+        let expected: Cow<str> = Cow::Owned("\"vnd.abc\"".into());
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn quoting_classifier_with_custom_class() {
+        let classify = |bch| {
+            <MySpec as GeneralQSSpec>::Quoting::classify_for_quoting(
+                PartialCodePoint::from_utf8_byte(bch))
+        };
+        assert_eq!(classify(b'a'), QuotingClass::QText);
+        assert_eq!(classify(b' '), QuotingClass::QText);
+        assert_eq!(classify(b'"'), QuotingClass::NeedsQuoting);
+        assert_eq!(classify(b'\\'), QuotingClass::NeedsQuoting);
+        assert_eq!(classify(b'\0'), QuotingClass::Invalid);
+
+        let res = qs::quote::<MySpec>("a \"b\"").unwrap();
+        assert_eq!(res, "\"a \\\"b\\\"\"");
+    }
+}
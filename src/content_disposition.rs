@@ -0,0 +1,1690 @@
+use std::borrow::Cow;
+use std::char;
+use std::collections::{HashMap, HashSet};
+use std::error::{Error as StdError};
+use std::fmt::{self, Display};
+use std::str::{self, FromStr};
+
+use lut::Table;
+use penc::{percent_decode, utf8_percent_encode};
+use qs::{self, spec::GeneralQSSpec};
+use unicode_normalization::UnicodeNormalization;
+
+use quoted_string::{
+    AnyParsingImpl, AnyQuoting,
+    NormalParsingImpl, NormalQuoting,
+    MimeParsingUtf8, NormalUtf8Quoting,
+    HttpTokenValidator,
+    SuspiciousKind,
+    EmissionMode, is_safe_for_emission, quote_for_emission, quote_if_needed_for_emission
+};
+use lookup_tables::{MediaTypeChars, CText};
+use limits::{Limits, LimitKind, LimitExceeded};
+use percent_encoding::AttrCharEncodeSet;
+use scan::{find_first_not_http_token, find_first_not_token};
+
+/// the maximal length (in bytes) of a sanitized filename, the limit of most file systems
+pub const MAX_FILENAME_LENGTH: usize = 255;
+
+/// the spec used to unquote (legacy) parameter values
+///
+/// This accepts any char, including non us-ascii chars which are send as raw utf-8 by
+/// browsers and control chars (which are removed when sanitizing).
+#[derive(Copy, Clone, Debug)]
+struct LenientSpec;
+
+impl GeneralQSSpec for LenientSpec {
+    type Quoting = AnyQuoting;
+    type Parsing = AnyParsingImpl;
+}
+
+/// a parameter value as it appears in the header
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum RawValue<'a> {
+    Token(&'a str),
+    /// the quoted-string including the surrounding `'"'`
+    Quoted(&'a str)
+}
+
+/// a (name, value) pair of a parameter as it appears in the header
+pub(crate) type RawParam<'a> = (&'a str, RawValue<'a>);
+
+/// splits the parameters of a header value, skipping the disposition (or media) type
+///
+/// This is lenient wrt. the legacy forms seen in practice, i.e. it accepts ws around
+/// the `'='`, tokens containing non-token chars (like ws) and ends unterminated
+/// quoted-strings at the end of the input. Parameters without a `'='` are skipped.
+///
+/// The limits are checked wrt. the raw parameters, e.g. the value length is the length
+/// of the quoted-string (including the `'"'`) not of it's content. Comments are not
+/// skipped (legacy tokens like `a (1).txt` contain `'('`) but their depth is still checked.
+pub(crate) fn raw_params<'a>(header: &'a str, limits: &Limits) -> Result<Vec<RawParam<'a>>, LimitExceeded> {
+    limits.check_header(header.as_bytes())?;
+    limits.check_comment_depth(header.as_bytes())?;
+    let index_of = |rest: &str| header.len() - rest.len();
+    let mut params = Vec::new();
+    let mut rest = match header.find(';') {
+        Some(idx) => &header[idx..],
+        None => return Ok(params)
+    };
+    loop {
+        rest = rest.trim_start_matches(|ch: char| ch == ';' || ch.is_whitespace());
+        if rest.is_empty() {
+            return Ok(params);
+        }
+        let name_start = index_of(rest);
+        let name_end = rest.find(['=', ';']).unwrap_or(rest.len());
+        let name = rest[..name_end].trim();
+        rest = &rest[name_end..];
+        if !rest.starts_with('=') {
+            continue;
+        }
+        limits.check(LimitKind::ParamCount, params.len() + 1, name_start)?;
+        limits.check_length(LimitKind::ParamNameLength, name_start, name.len())?;
+        rest = rest[1..].trim_start();
+        let value_start = index_of(rest);
+        let value =
+            if rest.starts_with('"') {
+                match qs::parse::<LenientSpec>(rest) {
+                    Ok(parsed) => {
+                        rest = parsed.tail;
+                        RawValue::Quoted(parsed.quoted_string)
+                    },
+                    Err(_) => {
+                        // unterminated or containing control chars, take the rest as is
+                        let end = rest.find(';').unwrap_or(rest.len());
+                        let value = RawValue::Token(rest[1..end].trim_end_matches('"'));
+                        rest = &rest[end..];
+                        value
+                    }
+                }
+            } else {
+                let end = rest.find(';').unwrap_or(rest.len());
+                let value = RawValue::Token(rest[..end].trim_end());
+                rest = &rest[end..];
+                value
+            };
+        limits.check_length(LimitKind::ParamValueLength, value_start, index_of(rest) - value_start)?;
+        params.push((name, value));
+    }
+}
+
+/// the content of the value, i.e. the unquoted value of a quoted-string
+///
+/// Legacy clients (e.g. old versions of IE) send full windows paths in quoted-strings
+/// without escaping the `'\\'`, so if the quoted-string contains a `'\\'` which is not
+/// followed by `'"'` or `'\\'` it is taken literally (while `\"` and `\\` still are
+/// unescaped). Otherwise the quoted-string is unquoted with `qs::to_content`.
+pub(crate) fn content<'a>(value: RawValue<'a>) -> Cow<'a, str> {
+    match value {
+        RawValue::Token(token) => Cow::Borrowed(token),
+        RawValue::Quoted(quoted) => {
+            let inner = &quoted[1..quoted.len() - 1];
+            if has_literal_backslash(inner) {
+                Cow::Owned(unescape_legacy(inner))
+            } else {
+                qs::to_content::<LenientSpec>(quoted).unwrap_or(Cow::Borrowed(inner))
+            }
+        }
+    }
+}
+
+/// returns true if a `'\\'` in the (inner part of the) quoted-string is not followed
+/// by `'"'` or `'\\'`, i.e. it is not part of a `\"` or `\\` quoted-pair
+fn has_literal_backslash(inner: &str) -> bool {
+    let mut escaped = false;
+    for bch in inner.bytes() {
+        if escaped {
+            if bch != b'"' && bch != b'\\' {
+                return true;
+            }
+            escaped = false;
+        } else if bch == b'\\' {
+            escaped = true;
+        }
+    }
+    escaped
+}
+
+/// unescapes `\"` and `\\`, keeping any other `'\\'` literally
+fn unescape_legacy(inner: &str) -> String {
+    let mut out = String::with_capacity(inner.len());
+    let mut escaped = false;
+    for ch in inner.chars() {
+        if escaped {
+            if ch != '"' && ch != '\\' {
+                out.push('\\');
+            }
+            out.push(ch);
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else {
+            out.push(ch);
+        }
+    }
+    if escaped {
+        out.push('\\');
+    }
+    out
+}
+
+/// decodes the bytes wrt. the charset, supporting `utf-8`, `iso-8859-1` and `us-ascii`
+fn decode_charset(charset: &str, bytes: &[u8]) -> Option<String> {
+    if charset.eq_ignore_ascii_case("utf-8") || charset.eq_ignore_ascii_case("utf8") {
+        String::from_utf8(bytes.to_owned()).ok()
+    } else if charset.eq_ignore_ascii_case("iso-8859-1") || charset.eq_ignore_ascii_case("latin1") {
+        Some(bytes.iter().map(|&bch| bch as char).collect())
+    } else if charset.eq_ignore_ascii_case("us-ascii") && bytes.is_ascii() {
+        Some(bytes.iter().map(|&bch| bch as char).collect())
+    } else {
+        None
+    }
+}
+
+/// decodes a RFC8187 `ext-value` (`charset "'" [ language ] "'" value-chars`)
+fn decode_ext_value(ext_value: &str) -> Option<String> {
+    let mut parts = ext_value.splitn(3, '\'');
+    let charset = parts.next()?;
+    let _language = parts.next()?;
+    let encoded = parts.next()?;
+    let bytes: Vec<u8> = percent_decode(encoded.as_bytes()).collect();
+    decode_charset(charset, &bytes)
+}
+
+/// decodes RFC2231 parameter continuations (`filename*0*=utf-8''a%20; filename*1=b`)
+fn decode_continuations(params: &[RawParam], name: &str) -> Option<String> {
+    // index all sections in one pass, if a section appears more than once the first one is used
+    let mut sections = HashMap::new();
+    for &(pname, value) in params.iter() {
+        let rest = match strip_prefix_ignore_case(pname, name).and_then(|rest| rest.strip_prefix('*')) {
+            Some(rest) => rest,
+            None => continue
+        };
+        let (number, encoded) = match rest.strip_suffix('*') {
+            Some(number) => (number, true),
+            None => (rest, false)
+        };
+        let is_number = !number.is_empty() && number.bytes().all(|bch| bch.is_ascii_digit());
+        if !is_number || (number != "0" && number.starts_with('0')) {
+            continue;
+        }
+        if let Ok(idx) = number.parse::<usize>() {
+            sections.entry(idx).or_insert((value, encoded));
+        }
+    }
+    let mut charset = None;
+    let mut bytes = Vec::new();
+    for idx in 0.. {
+        let (value, encoded) = match sections.get(&idx) {
+            Some(&section) => section,
+            None => break
+        };
+        let value = content(value);
+        if encoded {
+            let value =
+                if idx == 0 {
+                    let mut parts = value.splitn(3, '\'');
+                    charset = parts.next().map(|charset| charset.to_owned());
+                    parts.nth(1)?.to_owned()
+                } else {
+                    value.into_owned()
+                };
+            bytes.extend(percent_decode(value.as_bytes()));
+        } else {
+            bytes.extend_from_slice(value.as_bytes());
+        }
+    }
+    if bytes.is_empty() {
+        return None;
+    }
+    match charset {
+        Some(charset) => decode_charset(&charset, &bytes),
+        None => String::from_utf8(bytes).ok()
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(input: &'a str, prefix: &str) -> Option<&'a str> {
+    if input.len() >= prefix.len()
+        && input.is_char_boundary(prefix.len())
+        && input[..prefix.len()].eq_ignore_ascii_case(prefix)
+    {
+        Some(&input[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+/// decodes base64 (with or without padding), used for RFC2047 `B` encoded-words
+///
+/// Returns `None` for invalid padding or if the unused bits of the last char are not zero,
+/// as such input is not the (only) encoding of any bytes.
+fn decode_base64(input: &str) -> Option<Vec<u8>> {
+    let data = input.trim_end_matches('=');
+    let padding = input.len() - data.len();
+    if data.len() % 4 == 1 || (padding > 0 && (padding > 2 || input.len() % 4 != 0)) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(data.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for bch in data.bytes() {
+        let val = match bch {
+            b'A'..=b'Z' => bch - b'A',
+            b'a'..=b'z' => bch - b'a' + 26,
+            b'0'..=b'9' => bch - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None
+        };
+        acc = (acc << 6) | val as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    if acc == 0 {
+        Some(out)
+    } else {
+        None
+    }
+}
+
+/// decodes the text of a RFC2047 `Q` encoded-word, i.e. `=XX` and `'_'` (as `' '`)
+///
+/// Any other char (including `'%'`) is taken literally, a `'='` not followed by two
+/// hex digits is invalid.
+fn decode_q(input: &str) -> Option<Vec<u8>> {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'_' => out.push(b' '),
+            b'=' => {
+                let hex = bytes.get(idx + 1..idx + 3).filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))?;
+                // SAFE: the bytes are hex digits, i.e. us-ascii
+                let hex = str::from_utf8(hex).expect("[BUG] hex digits are us-ascii");
+                out.push(u8::from_str_radix(hex, 16).expect("[BUG] two hex digits are a u8"));
+                idx += 2;
+            },
+            bch => out.push(bch)
+        }
+        idx += 1;
+    }
+    Some(out)
+}
+
+/// decodes a single RFC2047 encoded-word (`=?charset?encoding?text?=`)
+fn decode_encoded_word(word: &str) -> Option<String> {
+    let inner = word.strip_prefix("=?")?.strip_suffix("?=")?;
+    let mut parts = inner.splitn(3, '?');
+    // RFC2231 allows a language after a '*'
+    let charset = parts.next()?.split('*').next()?;
+    let encoding = parts.next()?;
+    let text = parts.next()?;
+    let bytes =
+        if encoding.eq_ignore_ascii_case("b") {
+            decode_base64(text)?
+        } else if encoding.eq_ignore_ascii_case("q") {
+            decode_q(text)?
+        } else {
+            return None;
+        };
+    decode_charset(charset, &bytes)
+}
+
+/// decodes RFC2047 encoded-words in a (legacy) `filename` parameter
+///
+/// Ws between two encoded-words is removed, input without encoded-words is borrowed.
+fn decode_encoded_words(input: &str) -> Cow<'_, str> {
+    if !input.contains("=?") {
+        return Cow::Borrowed(input);
+    }
+    let mut out = String::with_capacity(input.len());
+    let mut last_was_word = false;
+    let mut pending_ws = "";
+    let mut rest = input;
+    while !rest.is_empty() {
+        let ws_end = rest.find(|ch: char| !ch.is_whitespace()).unwrap_or(rest.len());
+        if ws_end > 0 {
+            pending_ws = &rest[..ws_end];
+            rest = &rest[ws_end..];
+            continue;
+        }
+        let word_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let word = &rest[..word_end];
+        match decode_encoded_word(word) {
+            Some(decoded) => {
+                if !last_was_word {
+                    out.push_str(pending_ws);
+                }
+                out.push_str(&decoded);
+                last_was_word = true;
+            },
+            None => {
+                out.push_str(pending_ws);
+                out.push_str(word);
+                last_was_word = false;
+            }
+        }
+        pending_ws = "";
+        rest = &rest[word_end..];
+    }
+    out.push_str(pending_ws);
+    Cow::Owned(out)
+}
+
+/// extracts the (decoded) filename from a Content-Disposition header value
+///
+/// The filename is looked up in following order:
+///
+/// 1. `filename*` (RFC8187/RFC5987 `ext-value`, `utf-8` and `iso-8859-1` are supported)
+/// 2. `filename*0*`/`filename*0`... (RFC2231 continuations, as used in mails)
+/// 3. `filename` (RFC6266), which may contain raw utf-8 (as send by browsers), unescaped
+///    `'\\'` (as send by old IE versions) or RFC2047 encoded-words (as send by old mail clients)
+///
+/// If a form can not be decoded the next one is used. Parameter names are matched case
+/// insensitive and the parsing is lenient wrt. malformed headers, as it's meant to extract
+/// whatever filename a client intended.
+///
+/// The default `Limits` are checked, if one is exceeded `None` is returned (use
+/// `extract_filename_with_limits` to tell this apart).
+///
+/// **The returned filename is not sanitized**, it can contain paths, control chars etc.
+/// use `safe_filename` if it's used to create a file.
+pub fn extract_filename(header: &str) -> Option<String> {
+    extract_filename_with_limits(header, &Limits::new()).unwrap_or(None)
+}
+
+/// like `extract_filename` but checking the limits (e.g. the number of parameters) first
+///
+/// # Error
+///
+/// if the header exceeds a limit, even if the filename parameter would be within them
+pub fn extract_filename_with_limits(header: &str, limits: &Limits)
+    -> Result<Option<String>, LimitExceeded>
+{
+    let params = raw_params(header, limits)?;
+    let find = |name: &str| {
+        params.iter()
+            .find(|&&(pname, _)| pname.eq_ignore_ascii_case(name))
+            .map(|&(_, value)| content(value))
+    };
+    if let Some(name) = find("filename*").and_then(|value| decode_ext_value(&value)) {
+        return Ok(Some(name));
+    }
+    if let Some(name) = decode_continuations(&params, "filename") {
+        return Ok(Some(name));
+    }
+    Ok(find("filename").map(|value| decode_encoded_words(&value).into_owned()))
+}
+
+/// extracts the filename from a Content-Disposition header value and sanitizes it
+///
+/// This is `extract_filename` followed by `FilenameSanitizer::new().sanitize`, so `None`
+/// is returned if the header exceeds the default `Limits`, too.
+///
+/// # Example
+///
+/// ```
+/// use media_type_impl_utils::content_disposition::safe_filename;
+///
+/// let header = "attachment; filename=\"../../etc/passwd\"";
+/// assert_eq!(safe_filename(header).unwrap(), "passwd");
+///
+/// let header = "attachment; filename=\"EURO rates\"; filename*=utf-8''%e2%82%ac%20rates";
+/// assert_eq!(safe_filename(header).unwrap(), "\u{20ac} rates");
+/// ```
+pub fn safe_filename(header: &str) -> Option<String> {
+    safe_filename_with_limits(header, &Limits::new()).unwrap_or(None)
+}
+
+/// like `safe_filename` but with the given limits
+///
+/// # Error
+///
+/// if the header exceeds a limit, see `extract_filename_with_limits`
+pub fn safe_filename_with_limits(header: &str, limits: &Limits)
+    -> Result<Option<String>, LimitExceeded>
+{
+    let name = extract_filename_with_limits(header, limits)?;
+    Ok(name.and_then(|name| FilenameSanitizer::new().sanitize(&name)))
+}
+
+/// the names of the parameters `ContentDisposition` has fields for (without `'*'`)
+const RESERVED_PARAM_NAMES: &[&str] = &[
+    "filename", "name", "size", "creation-date", "modification-date", "read-date"
+];
+
+/// the names windows reserves for devices (with any extension)
+const WINDOWS_DEVICE_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9"
+];
+
+/// Sanitizes filenames (e.g. from a Content-Disposition header) so that they can be used
+/// to create a file in a given directory on any common file system.
+///
+/// The sanitization works following:
+///
+/// 1. path components are stripped, i.e. only the part after the last `'/'` or `'\\'` is kept
+/// 2. control chars (C0, DEL, C1) as well as bidi controls, zero-width chars and unassigned
+///    code points (see `SuspiciousKind`) are removed
+/// 3. unicode ws is replaced with `' '`, chars reserved on windows (`<>:"|?*`) and chars
+///    which look like path separators (e.g. `U+2215 DIVISION SLASH`) with the replacement
+///    char (`'_'` by default)
+/// 4. the name is normalized to NFC (so that e.g. `"e\u{301}"` and `"\u{e9}"` are the same name)
+/// 5. leading and trailing ws and `'.'` are removed (which includes `"."` and `".."`)
+/// 6. the name is truncated to the max length (in bytes), keeping the extension if possible
+/// 7. names reserved on windows (e.g. `"CON"`, `"nul.txt"`) are prefixed with the
+///    replacement char (truncating it again if needed)
+///
+/// If nothing is left `None` is returned.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct FilenameSanitizer {
+    max_length: usize,
+    replacement: char
+}
+
+impl Default for FilenameSanitizer {
+    fn default() -> Self {
+        FilenameSanitizer { max_length: MAX_FILENAME_LENGTH, replacement: '_' }
+    }
+}
+
+impl FilenameSanitizer {
+
+    /// create a new sanitizer with a max length of `MAX_FILENAME_LENGTH` and `'_'` as replacement
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// returns a sanitizer truncating names to the given length (in bytes)
+    pub fn with_max_length(self, max_length: usize) -> Self {
+        FilenameSanitizer { max_length, ..self }
+    }
+
+    /// returns a sanitizer using the given char to replace reserved chars
+    ///
+    /// # Panic
+    ///
+    /// if the replacement char would be removed or replaced by the sanitizer itself
+    pub fn with_replacement(self, replacement: char) -> Self {
+        assert!(
+            !is_removed(replacement) && !is_reserved(replacement)
+                && !replacement.is_whitespace() && replacement != '.',
+            "[BUG] invalid replacement char: {:?}", replacement
+        );
+        FilenameSanitizer { replacement, ..self }
+    }
+
+    /// the max length (in bytes) of a sanitized name
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    /// sanitizes the given filename, returns `None` if nothing is left
+    pub fn sanitize(&self, name: &str) -> Option<String> {
+        let base = match name.rfind(['/', '\\']) {
+            Some(idx) => &name[idx + 1..],
+            None => name
+        };
+        let cleaned: String = base.chars()
+            .filter(|&ch| !is_removed(ch))
+            .map(|ch| {
+                if is_reserved(ch) {
+                    self.replacement
+                } else if ch.is_whitespace() {
+                    ' '
+                } else {
+                    ch
+                }
+            })
+            .nfc()
+            .collect();
+        let trimmed = cleaned.trim_matches(&[' ', '.'][..]);
+        if trimmed.is_empty() {
+            return None;
+        }
+        let mut out = trimmed.to_owned();
+        self.truncate(&mut out);
+        // checked after truncating, as truncating can turn e.g. "CONxxx.pdf" into "CON.pdf"
+        if is_windows_device_name(&out) {
+            out.insert(0, self.replacement);
+            self.truncate(&mut out);
+        }
+        if out.is_empty() {
+            None
+        } else {
+            Some(out)
+        }
+    }
+
+    fn truncate(&self, name: &mut String) {
+        if name.len() <= self.max_length {
+            return;
+        }
+        // keep (short) extensions, e.g. ".pdf" or ".tar.gz"
+        let extension = match name.rfind('.') {
+            Some(idx) if idx > 0 && name.len() - idx <= 16 && name.len() - idx < self.max_length => {
+                name[idx..].to_owned()
+            },
+            _ => String::new()
+        };
+        let mut end = self.max_length - extension.len();
+        while !name.is_char_boundary(end) {
+            end -= 1;
+        }
+        name.truncate(end);
+        let stem_len = name.trim_end_matches([' ', '.']).len();
+        name.truncate(stem_len);
+        if !name.is_empty() {
+            name.push_str(&extension);
+        }
+    }
+}
+
+/// returns true if the name (ignoring the extension) is reserved for a device on windows
+fn is_windows_device_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    WINDOWS_DEVICE_NAMES.iter().any(|dev| dev.eq_ignore_ascii_case(stem))
+}
+
+/// chars removed by the `FilenameSanitizer`
+fn is_removed(ch: char) -> bool {
+    ch.is_control() || SuspiciousKind::of(ch).is_some()
+}
+
+/// chars replaced by the `FilenameSanitizer`
+fn is_reserved(ch: char) -> bool {
+    match ch {
+        '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => true,
+        // chars looking like path separators
+        '\u{2044}' | '\u{2215}' | '\u{2216}' | '\u{29F8}' | '\u{29F9}' | '\u{FF0F}' | '\u{FF3C}' => true,
+        _ => false
+    }
+}
+
+/// the grammar a Content-Disposition header is parsed wrt.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum DispositionSyntax {
+    /// the (modern) http grammar (RFC6266, RFC7230)
+    Http,
+    /// the internationalized mime grammar (RFC2183, RFC6532), i.e. quoted-strings can
+    /// contain utf-8 and FWS and comments are allowed where ws is
+    Mime
+}
+
+#[derive(Copy, Clone, Debug)]
+struct HttpSpec;
+
+impl GeneralQSSpec for HttpSpec {
+    type Quoting = NormalQuoting;
+    type Parsing = NormalParsingImpl;
+}
+
+#[derive(Copy, Clone, Debug)]
+struct MimeSpec;
+
+impl GeneralQSSpec for MimeSpec {
+    type Quoting = NormalUtf8Quoting;
+    type Parsing = MimeParsingUtf8;
+}
+
+/// the disposition type of a Content-Disposition header (compared case insensitive)
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum DispositionType {
+    /// `inline` (RFC6266, RFC2183)
+    Inline,
+    /// `attachment` (RFC6266, RFC2183)
+    Attachment,
+    /// `form-data` (RFC7578)
+    FormData,
+    /// any other (extension) disposition type, in lower case
+    Other(String)
+}
+
+impl DispositionType {
+
+    /// the disposition type for the given (token), e.g. `"Attachment"`
+    pub fn from_token(token: &str) -> Self {
+        if token.eq_ignore_ascii_case("inline") {
+            DispositionType::Inline
+        } else if token.eq_ignore_ascii_case("attachment") {
+            DispositionType::Attachment
+        } else if token.eq_ignore_ascii_case("form-data") {
+            DispositionType::FormData
+        } else {
+            DispositionType::Other(token.to_ascii_lowercase())
+        }
+    }
+
+    /// the disposition type as (lower case) str
+    pub fn as_str(&self) -> &str {
+        match *self {
+            DispositionType::Inline => "inline",
+            DispositionType::Attachment => "attachment",
+            DispositionType::FormData => "form-data",
+            DispositionType::Other(ref other) => other
+        }
+    }
+}
+
+/// the reason a Content-Disposition header could not be parsed
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum DispositionErrorKind {
+    /// the disposition type is missing or not a token
+    InvalidDispositionType,
+    /// a `';'` was expected
+    ExpectedSemicolon,
+    /// a parameter name is missing or not a token
+    InvalidParamName,
+    /// a `'='` was expected after the parameter name
+    ExpectedEquals,
+    /// a parameter value is missing, not a token and not a valid quoted-string
+    InvalidValue,
+    /// a `name*` parameter is not a valid `ext-value` (or uses a unsupported charset)
+    InvalidExtValue,
+    /// a parameter appears more than once
+    DuplicateParam,
+    /// the `size` parameter is not a (decimal) number
+    InvalidSize,
+    /// a comment (in the mime grammar) is not valid or not closed
+    InvalidComment,
+    /// a limit was exceeded (see `ContentDisposition::parse_with_limits`)
+    LimitExceeded(LimitKind)
+}
+
+/// error returned if a Content-Disposition header could not be parsed
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct DispositionParseError {
+    /// the reason of the error
+    pub kind: DispositionErrorKind,
+    /// the (byte) index in the input at which the error was found
+    pub index: usize
+}
+
+impl Display for DispositionParseError {
+    fn fmt(&self, fter: &mut fmt::Formatter) -> fmt::Result {
+        use self::DispositionErrorKind::*;
+        if let DispositionErrorKind::LimitExceeded(kind) = self.kind {
+            return write!(fter, "{} at index {} of Content-Disposition", kind.description(), self.index);
+        }
+        let desc = match self.kind {
+            InvalidDispositionType => "missing or invalid disposition type",
+            ExpectedSemicolon => "expected ';'",
+            InvalidParamName => "missing or invalid parameter name",
+            ExpectedEquals => "expected '=' after parameter name",
+            InvalidValue => "missing or invalid parameter value",
+            InvalidExtValue => "invalid or unsupported ext-value",
+            DuplicateParam => "duplicate parameter",
+            InvalidSize => "size parameter is not a number",
+            InvalidComment => "invalid or unclosed comment",
+            LimitExceeded(_) => unreachable!()
+        };
+        write!(fter, "{} at index {} of Content-Disposition", desc, self.index)
+    }
+}
+
+impl StdError for DispositionParseError {}
+
+impl From<LimitExceeded> for DispositionParseError {
+    fn from(err: LimitExceeded) -> Self {
+        DispositionParseError { kind: DispositionErrorKind::LimitExceeded(err.kind), index: err.index }
+    }
+}
+
+/// error returned if a `ContentDisposition` could not be written
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum DispositionBuildError {
+    /// the (extension) disposition type is not a token
+    InvalidDispositionType,
+    /// the name of a (extension) parameter is not a token, is the name of a parameter
+    /// with a field of it's own (e.g. `filename`) or is used more than once (with or
+    /// without `'*'`)
+    InvalidParamName(String),
+    /// the value of the parameter contains a `'\r'`, `'\n'` or `'\0'` (with `EmissionMode::Refuse`)
+    UnsafeValue(String)
+}
+
+impl Display for DispositionBuildError {
+    fn fmt(&self, fter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DispositionBuildError::InvalidDispositionType =>
+                fter.write_str("disposition type is not a token"),
+            DispositionBuildError::InvalidParamName(ref name) =>
+                write!(fter, "invalid, reserved or duplicate parameter name: {:?}", name),
+            DispositionBuildError::UnsafeValue(ref name) =>
+                write!(fter, "value of parameter {:?} contains '\\r', '\\n' or '\\0'", name)
+        }
+    }
+}
+
+impl StdError for DispositionBuildError {}
+
+/// a parsed Content-Disposition header (RFC6266 for http, RFC2183 for mime)
+///
+/// `filename*` and other `name*` parameters (RFC8187/RFC2231 `ext-value`s) are decoded,
+/// `utf-8` and `iso-8859-1` are supported. The dates (`creation-date` etc.) are kept as
+/// (unquoted) strings as they are not parsed. Parameter names are compared case insensitive,
+/// RFC2231 continuations are not supported (`extract_filename` does support them).
+///
+/// When writing the header (`to_header_value`) each parameter value is written as token if
+/// possible, else as quoted-string and if it can't be represented as quoted-string (e.g.
+/// because it is non us-ascii) as `ext-value`. Non us-ascii filenames are written as
+/// `filename*` with an us-ascii fallback in `filename` (RFC6266 appendix D), except for
+/// `form-data` which uses quoted-strings containing utf-8 (RFC7578 section 4.2) and never
+/// uses `filename*` (which RFC7578 forbids), instead `'\r'`, `'\n'` and `'"'` are percent
+/// encoded and values are always quoted without escaping `'\\'` like HTML does.
+///
+/// # Example
+///
+/// ```
+/// use media_type_impl_utils::content_disposition::{
+///     ContentDisposition, DispositionType, DispositionSyntax
+/// };
+///
+/// let header = "attachment; filename=\"EURO rates.txt\"; filename*=UTF-8''%E2%82%AC%20rates.txt";
+/// let parsed = ContentDisposition::parse(header, DispositionSyntax::Http).unwrap();
+/// assert_eq!(parsed.disposition_type, DispositionType::Attachment);
+/// assert_eq!(parsed.preferred_filename(), Some("\u{20ac} rates.txt"));
+///
+/// let cd = ContentDisposition::new(DispositionType::Attachment)
+///     .with_filename("\u{20ac} rates.txt")
+///     .with_size(1024);
+/// assert_eq!(
+///     cd.to_header_value().unwrap(),
+///     "attachment; filename=\"_ rates.txt\"; filename*=UTF-8''%E2%82%AC%20rates.txt; size=1024"
+/// );
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ContentDisposition {
+    /// the disposition type
+    pub disposition_type: DispositionType,
+    /// the `filename` parameter
+    pub filename: Option<String>,
+    /// the (decoded) `filename*` parameter
+    pub filename_ext: Option<String>,
+    /// the `name` parameter (the field name for `form-data`)
+    pub name: Option<String>,
+    /// the `creation-date` parameter (RFC2183)
+    pub creation_date: Option<String>,
+    /// the `modification-date` parameter (RFC2183)
+    pub modification_date: Option<String>,
+    /// the `read-date` parameter (RFC2183)
+    pub read_date: Option<String>,
+    /// the `size` parameter (RFC2183)
+    pub size: Option<u64>,
+    /// all other parameters as (lower case name, value) pairs, `name*` parameters are decoded
+    /// and stored with the `'*'`
+    pub params: Vec<(String, String)>
+}
+
+impl ContentDisposition {
+
+    /// create a new Content-Disposition without any parameters
+    pub fn new(disposition_type: DispositionType) -> Self {
+        ContentDisposition {
+            disposition_type,
+            filename: None,
+            filename_ext: None,
+            name: None,
+            creation_date: None,
+            modification_date: None,
+            read_date: None,
+            size: None,
+            params: Vec::new()
+        }
+    }
+
+    /// returns it with the `filename` parameter set
+    pub fn with_filename<S: Into<String>>(self, filename: S) -> Self {
+        ContentDisposition { filename: Some(filename.into()), ..self }
+    }
+
+    /// returns it with the `name` parameter set
+    pub fn with_name<S: Into<String>>(self, name: S) -> Self {
+        ContentDisposition { name: Some(name.into()), ..self }
+    }
+
+    /// returns it with the `size` parameter set
+    pub fn with_size(self, size: u64) -> Self {
+        ContentDisposition { size: Some(size), ..self }
+    }
+
+    /// returns it with a additional (extension) parameter
+    pub fn with_param<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+        self.params.push((name.into(), value.into()));
+        self
+    }
+
+    /// the filename which should be used, i.e. `filename*` if present else `filename`
+    ///
+    /// Note that the filename is not sanitized, see `FilenameSanitizer`.
+    pub fn preferred_filename(&self) -> Option<&str> {
+        self.filename_ext.as_ref().or(self.filename.as_ref()).map(|name| &**name)
+    }
+
+    /// parses a Content-Disposition header value
+    ///
+    /// In the mime grammar comments are skipped like ws. The default `Limits` are checked,
+    /// see `parse_with_limits`.
+    ///
+    /// # Error
+    ///
+    /// if the header is not valid wrt. the syntax, contains duplicate parameters, a
+    /// `ext-value` which can not be decoded or exceeds a limit
+    pub fn parse(input: &str, syntax: DispositionSyntax) -> Result<Self, DispositionParseError> {
+        ContentDisposition::parse_with_limits(input, syntax, &Limits::new())
+    }
+
+    /// parses a Content-Disposition header value, checking the limits
+    ///
+    /// The length and number of folds are checked before parsing, the parameter limits
+    /// wrt. the parameters as they appear in the input (e.g. a quoted-string value is
+    /// counted including the `'"'` and quoted-pairs).
+    ///
+    /// # Error
+    ///
+    /// like `parse`, a `DispositionErrorKind::LimitExceeded` is returned at the index at
+    /// which the limit was exceeded
+    pub fn parse_with_limits(input: &str, syntax: DispositionSyntax, limits: &Limits)
+        -> Result<Self, DispositionParseError>
+    {
+        limits.check_header(input.as_bytes())?;
+        let mut parser = DispositionParser { input, pos: 0, syntax, limits: *limits };
+        parser.skip_ws()?;
+        let disposition_type = parser.token(DispositionErrorKind::InvalidDispositionType)?;
+        let mut cd = ContentDisposition::new(DispositionType::from_token(disposition_type));
+        let mut seen = HashSet::new();
+        loop {
+            parser.skip_ws()?;
+            if parser.rest().is_empty() {
+                return Ok(cd);
+            }
+            parser.expect(b';', DispositionErrorKind::ExpectedSemicolon)?;
+            parser.skip_ws()?;
+            // tolerate a trailing ';'
+            if parser.rest().is_empty() {
+                return Ok(cd);
+            }
+            let name_start = parser.pos;
+            limits.check(LimitKind::ParamCount, seen.len() + 1, name_start)?;
+            let name = parser.token(DispositionErrorKind::InvalidParamName)?.to_ascii_lowercase();
+            limits.check_length(LimitKind::ParamNameLength, name_start, name.len())?;
+            // RFC6266 uses the implied LWS rule of RFC2616, so ws is allowed around the '='
+            // with either syntax
+            parser.skip_ws()?;
+            parser.expect(b'=', DispositionErrorKind::ExpectedEquals)?;
+            parser.skip_ws()?;
+            let value_start = parser.pos;
+            let value = parser.value()?;
+            limits.check_length(LimitKind::ParamValueLength, value_start, parser.pos - value_start)?;
+            if seen.contains(&name) {
+                return Err(parser.error_at(DispositionErrorKind::DuplicateParam, name_start));
+            }
+            let value =
+                if name.ends_with('*') {
+                    decode_ext_value(&value)
+                        .ok_or_else(|| parser.error_at(DispositionErrorKind::InvalidExtValue, value_start))?
+                } else {
+                    value
+                };
+            match &*name {
+                "filename" => cd.filename = Some(value),
+                "filename*" => cd.filename_ext = Some(value),
+                "name" => cd.name = Some(value),
+                "creation-date" => cd.creation_date = Some(value),
+                "modification-date" => cd.modification_date = Some(value),
+                "read-date" => cd.read_date = Some(value),
+                "size" => {
+                    // `u64::from_str` accepts a leading '+' but RFC2183 uses `1*DIGIT`
+                    let is_digits = !value.is_empty() && value.bytes().all(|bch| bch.is_ascii_digit());
+                    let size = if is_digits { value.parse().ok() } else { None };
+                    let size = size
+                        .ok_or_else(|| parser.error_at(DispositionErrorKind::InvalidSize, value_start))?;
+                    cd.size = Some(size);
+                },
+                _ => cd.params.push((name.clone(), value))
+            }
+            seen.insert(name);
+        }
+    }
+
+    /// writes the Content-Disposition header value
+    ///
+    /// This is `to_header_value_with_mode(EmissionMode::ExtValue)`.
+    ///
+    /// # Error
+    ///
+    /// if the disposition type or the name of a extension parameter is not a token
+    pub fn to_header_value(&self) -> Result<String, DispositionBuildError> {
+        self.to_header_value_with_mode(EmissionMode::ExtValue)
+    }
+
+    /// writes the Content-Disposition header value, handling unsafe values wrt. `mode`
+    ///
+    /// A value containing a `'\r'`, `'\n'` or `'\0'` is never written as quoted-string, with
+    /// `EmissionMode::ExtValue` it is written as `ext-value` (`filename` as us-ascii fallback
+    /// with `'_'` replacing them, plus `filename*`) with `EmissionMode::Refuse` it's a error.
+    /// For `form-data` they are percent encoded in the quoted-string instead of using a
+    /// `ext-value` (RFC7578 section 4.2, like HTML does).
+    ///
+    /// # Error
+    ///
+    /// if the disposition type or the name of a extension parameter is not a token, a
+    /// extension parameter would be written twice (see `DispositionBuildError::InvalidParamName`)
+    /// or a value is not safe for emission and `mode` is `EmissionMode::Refuse`
+    pub fn to_header_value_with_mode(&self, mode: EmissionMode) -> Result<String, DispositionBuildError> {
+        let disposition_type = self.disposition_type.as_str();
+        if !is_http_token(disposition_type) {
+            return Err(DispositionBuildError::InvalidDispositionType);
+        }
+        if mode == EmissionMode::Refuse {
+            let unsafe_value = self.param_values().into_iter().find(|&(_, value)| !is_safe_for_emission(value));
+            if let Some((name, _)) = unsafe_value {
+                return Err(DispositionBuildError::UnsafeValue(name.to_owned()));
+            }
+        }
+        let form_data = self.disposition_type == DispositionType::FormData;
+        let mut out = String::from(disposition_type);
+        if let Some(ref name) = self.name {
+            write_param_or_ext(&mut out, "name", name, form_data);
+        }
+        match (&self.filename, &self.filename_ext) {
+            // RFC7578 forbids `filename*`, so only the preferred filename is written
+            _ if form_data => {
+                if let Some(filename) = self.preferred_filename() {
+                    write_param(&mut out, "filename", filename, true);
+                }
+            },
+            (Some(filename), &None) => {
+                if !write_param(&mut out, "filename", filename, false) {
+                    write_quoted(&mut out, "filename", &ascii_fallback(filename));
+                    write_ext_value(&mut out, "filename*", filename);
+                }
+            },
+            (filename, Some(filename_ext)) => {
+                let fallback = filename.as_ref().unwrap_or(filename_ext);
+                if !write_param(&mut out, "filename", fallback, false) {
+                    write_quoted(&mut out, "filename", &ascii_fallback(fallback));
+                }
+                write_ext_value(&mut out, "filename*", filename_ext);
+            },
+            (&None, &None) => {}
+        }
+        let dates = [
+            ("creation-date", &self.creation_date),
+            ("modification-date", &self.modification_date),
+            ("read-date", &self.read_date)
+        ];
+        for &(name, value) in dates.iter() {
+            if let Some(ref value) = *value {
+                write_param_or_ext(&mut out, name, value, form_data);
+            }
+        }
+        if let Some(size) = self.size {
+            out.push_str("; size=");
+            out.push_str(&size.to_string());
+        }
+        let mut seen = HashSet::new();
+        for (name, value) in self.params.iter() {
+            let plain_name = name.strip_suffix('*').unwrap_or(name);
+            if !is_http_token(plain_name) || plain_name.contains('*') {
+                return Err(DispositionBuildError::InvalidParamName(name.clone()));
+            }
+            // a non us-ascii `x` is written as `x*`, so `x` and `x*` are the same parameter
+            let plain_name = plain_name.to_ascii_lowercase();
+            if RESERVED_PARAM_NAMES.contains(&&*plain_name) || !seen.insert(plain_name) {
+                return Err(DispositionBuildError::InvalidParamName(name.clone()));
+            }
+            if name.ends_with('*') {
+                write_ext_value(&mut out, name, value);
+            } else {
+                write_param_or_ext(&mut out, name, value, form_data);
+            }
+        }
+        Ok(out)
+    }
+
+    /// all (name, value) pairs of parameters with string values
+    fn param_values(&self) -> Vec<(&str, &str)> {
+        let known = [
+            ("name", &self.name),
+            ("filename", &self.filename),
+            ("filename*", &self.filename_ext),
+            ("creation-date", &self.creation_date),
+            ("modification-date", &self.modification_date),
+            ("read-date", &self.read_date)
+        ];
+        known.iter()
+            .filter_map(|&(name, value)| value.as_deref().map(|value| (name, value)))
+            .chain(self.params.iter().map(|(name, value)| (&**name, &**value)))
+            .collect()
+    }
+}
+
+impl FromStr for ContentDisposition {
+    type Err = DispositionParseError;
+
+    /// parses the header wrt. the http grammar (with the default `Limits`)
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        ContentDisposition::parse(input, DispositionSyntax::Http)
+    }
+}
+
+struct DispositionParser<'a> {
+    input: &'a str,
+    pos: usize,
+    syntax: DispositionSyntax,
+    limits: Limits
+}
+
+impl<'a> DispositionParser<'a> {
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn error_at(&self, kind: DispositionErrorKind, index: usize) -> DispositionParseError {
+        DispositionParseError { kind, index }
+    }
+
+    fn error(&self, kind: DispositionErrorKind) -> DispositionParseError {
+        self.error_at(kind, self.pos)
+    }
+
+    /// skips ws (and FWS and comments in the mime grammar)
+    fn skip_ws(&mut self) -> Result<(), DispositionParseError> {
+        loop {
+            let rest = self.rest();
+            self.pos += rest.len() - rest.trim_start_matches([' ', '\t']).len();
+            if self.syntax == DispositionSyntax::Http {
+                return Ok(());
+            }
+            let rest = self.rest().as_bytes();
+            if is_fws(rest) {
+                self.pos += 2;
+            } else if rest.first() == Some(&b'(') {
+                self.skip_comment()?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// skips a (possible nested) comment starting at the current position
+    fn skip_comment(&mut self) -> Result<(), DispositionParseError> {
+        let start = self.pos;
+        let input = self.input.as_bytes();
+        let mut depth = 0;
+        while let Some(&bch) = input.get(self.pos) {
+            match bch {
+                b'(' => {
+                    depth += 1;
+                    self.limits.check(LimitKind::CommentDepth, depth, self.pos)?;
+                },
+                b')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.pos += 1;
+                        return Ok(());
+                    }
+                },
+                b'\\' => {
+                    let quotable = input.get(self.pos + 1)
+                        .map(|&bch| bch == b' ' || bch == b'\t' || (0x21..=0x7e).contains(&bch))
+                        .unwrap_or(false);
+                    if !quotable {
+                        return Err(self.error_at(DispositionErrorKind::InvalidComment, self.pos + 1));
+                    }
+                    self.pos += 1;
+                },
+                b'\r' => {
+                    if !is_fws(&input[self.pos..]) {
+                        return Err(self.error(DispositionErrorKind::InvalidComment));
+                    }
+                    self.pos += 1;
+                },
+                b' ' | b'\t' => {},
+                // non us-ascii chars are allowed like in quoted-strings (MimeParsingUtf8)
+                bch if bch > 0x7f || MediaTypeChars::check_at(bch as usize, CText) => {},
+                _ => return Err(self.error(DispositionErrorKind::InvalidComment))
+            }
+            self.pos += 1;
+        }
+        Err(self.error_at(DispositionErrorKind::InvalidComment, start))
+    }
+
+    fn expect(&mut self, bch: u8, kind: DispositionErrorKind) -> Result<(), DispositionParseError> {
+        if self.rest().as_bytes().first() == Some(&bch) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(kind))
+        }
+    }
+
+    fn token(&mut self, kind: DispositionErrorKind) -> Result<&'a str, DispositionParseError> {
+        let rest = self.rest();
+        let len = match self.syntax {
+            DispositionSyntax::Http => find_first_not_http_token(rest.as_bytes()),
+            DispositionSyntax::Mime => find_first_not_token(rest.as_bytes())
+        }.unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error(kind));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    /// parses a token or quoted-string, returning it's content
+    fn value(&mut self) -> Result<String, DispositionParseError> {
+        if !self.rest().starts_with('"') {
+            return self.token(DispositionErrorKind::InvalidValue).map(|token| token.to_owned());
+        }
+        let result = match self.syntax {
+            DispositionSyntax::Http => parse_quoted::<HttpSpec>(self.rest()),
+            DispositionSyntax::Mime => parse_quoted::<MimeSpec>(self.rest())
+        };
+        match result {
+            Ok((content, len)) => {
+                self.pos += len;
+                Ok(content)
+            },
+            Err(offset) => Err(self.error_at(DispositionErrorKind::InvalidValue, self.pos + offset))
+        }
+    }
+}
+
+/// returns true if the input starts with a FWS (`"\r\n"` followed by ws)
+fn is_fws(input: &[u8]) -> bool {
+    input.starts_with(b"\r\n") && (input.get(2) == Some(&b' ') || input.get(2) == Some(&b'\t'))
+}
+
+/// parses the quoted-string at the begin of the input returning it's content and length
+fn parse_quoted<Spec: GeneralQSSpec>(input: &str) -> Result<(String, usize), usize> {
+    let parsed = qs::parse::<Spec>(input).map_err(|(offset, _)| offset)?;
+    let content = qs::to_content::<Spec>(parsed.quoted_string).map_err(|_| 0usize)?;
+    Ok((content.into_owned(), parsed.quoted_string.len()))
+}
+
+fn is_http_token(input: &str) -> bool {
+    !input.is_empty() && find_first_not_http_token(input.as_bytes()).is_none()
+}
+
+/// writes the parameter as token or quoted-string, returns false if neither is possible
+///
+/// For `form-data` values are encoded with `encode_form_data` and always written as
+/// quoted-string (containing utf-8 if non us-ascii) without quoted-pairs, as HTML does and
+/// receivers expect (i.e. a `'\\'` is taken literally), so it never returns false.
+fn write_param(out: &mut String, name: &str, value: &str, form_data: bool) -> bool {
+    let written =
+        if form_data {
+            Some(Cow::Owned(format!("\"{}\"", encode_form_data(value))))
+        } else if value.is_ascii() {
+            quote_if_needed_for_emission::<HttpSpec, _>(value, &mut HttpTokenValidator::new()).ok()
+        } else {
+            None
+        };
+    match written {
+        Some(written) => {
+            out.push_str("; ");
+            out.push_str(name);
+            out.push('=');
+            out.push_str(&written);
+            true
+        },
+        None => false
+    }
+}
+
+/// percent encodes `'\r'`, `'\n'` and `'"'` like HTML does for `form-data` names and filenames
+///
+/// Other control chars (except `'\t'`) are percent encoded too, as they can not be part of
+/// a quoted-string. Note that a `'%'` is not encoded (neither is it by HTML).
+fn encode_form_data(value: &str) -> Cow<'_, str> {
+    let needs_encoding = |ch: char| ch == '"' || (ch.is_ascii_control() && ch != '\t');
+    if !value.contains(needs_encoding) {
+        return Cow::Borrowed(value);
+    }
+    let mut out = String::with_capacity(value.len() + 8);
+    for ch in value.chars() {
+        if needs_encoding(ch) {
+            out.push_str(&format!("%{:02X}", ch as u32));
+        } else {
+            out.push(ch);
+        }
+    }
+    Cow::Owned(out)
+}
+
+fn write_param_or_ext(out: &mut String, name: &str, value: &str, form_data: bool) {
+    if !write_param(out, name, value, form_data) {
+        write_ext_value(out, &format!("{}*", name), value);
+    }
+}
+
+/// writes a quoted-string, the value has to be printable us-ascii
+fn write_quoted(out: &mut String, name: &str, value: &str) {
+    let quoted = quote_for_emission::<HttpSpec>(value).expect("[BUG] fallback value can not be quoted");
+    out.push_str("; ");
+    out.push_str(name);
+    out.push('=');
+    out.push_str(&quoted);
+}
+
+/// writes a RFC8187 `ext-value` (the name has to include the `'*'`)
+fn write_ext_value(out: &mut String, name: &str, value: &str) {
+    out.push_str("; ");
+    out.push_str(name);
+    out.push_str("=UTF-8''");
+    out.extend(utf8_percent_encode(value, AttrCharEncodeSet));
+}
+
+/// replaces all chars which are not printable us-ascii with `'_'`
+fn ascii_fallback(value: &str) -> String {
+    value.chars()
+        .map(|ch| if (' '..='~').contains(&ch) { ch } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use limits::DEFAULT_MAX_PARAMS;
+    use super::*;
+
+    #[test]
+    fn raw_params_are_split() {
+        let params = raw_params("attachment; a=b;c = \"d;e\" ;; f; g=h i", &Limits::unlimited()).unwrap();
+        assert_eq!(params, vec![
+            ("a", RawValue::Token("b")),
+            ("c", RawValue::Quoted("\"d;e\"")),
+            ("g", RawValue::Token("h i"))
+        ]);
+        let raw_params = |header| raw_params(header, &Limits::unlimited()).unwrap();
+        assert_eq!(raw_params("inline"), vec![]);
+        assert_eq!(raw_params("inline; filename=\"abc"), vec![("filename", RawValue::Token("abc"))]);
+    }
+
+    #[test]
+    fn ext_value_wins() {
+        let header = "attachment; filename=\"a.txt\"; FILENAME*=UTF-8'en'%C3%A4.txt";
+        assert_eq!(extract_filename(header).unwrap(), "\u{e4}.txt");
+        let header = "attachment; filename*=iso-8859-1''%E4.txt; filename=\"a.txt\"";
+        assert_eq!(extract_filename(header).unwrap(), "\u{e4}.txt");
+    }
+
+    #[test]
+    fn falls_back_if_ext_value_can_not_be_decoded() {
+        let header = "attachment; filename*=koi8-r''%C1.txt; filename=\"a.txt\"";
+        assert_eq!(extract_filename(header).unwrap(), "a.txt");
+        let header = "attachment; filename*=utf-8''%FF.txt; filename=\"a.txt\"";
+        assert_eq!(extract_filename(header).unwrap(), "a.txt");
+    }
+
+    #[test]
+    fn rfc2231_continuations() {
+        let header = "attachment; filename*0*=utf-8''%E2%82%AC; filename*1=\" rates\"; filename*2*=%2Etxt";
+        assert_eq!(extract_filename(header).unwrap(), "\u{20ac} rates.txt");
+        let header = "attachment; filename*1=b; filename*0=a; filename*3=d";
+        assert_eq!(extract_filename(header).unwrap(), "ab");
+        let header = "attachment; filename*0=a; filename*01=x; filename*0=y; filename*1=b";
+        assert_eq!(extract_filename(header).unwrap(), "ab");
+        // the sections are indexed in one pass, so many of them are cheap
+        let mut header = String::from("attachment");
+        for idx in (0..20_000).rev() {
+            header.push_str(&format!("; filename*{}=a", idx));
+        }
+        assert_eq!(extract_filename(&header), None);
+        let name = extract_filename_with_limits(&header, &Limits::unlimited()).unwrap();
+        assert_eq!(name.unwrap(), "a".repeat(20_000));
+    }
+
+    #[test]
+    fn legacy_filenames() {
+        assert_eq!(extract_filename("attachment; filename=\"a \\\"b\\\".txt\"").unwrap(), "a \"b\".txt");
+        assert_eq!(extract_filename("form-data; name=x; filename=\"r\u{e9}sum\u{e9}.pdf\"").unwrap(),
+                   "r\u{e9}sum\u{e9}.pdf");
+        assert_eq!(extract_filename("attachment; filename=\"C:\\Users\\me\\a.txt\"").unwrap(),
+                   "C:\\Users\\me\\a.txt");
+        assert_eq!(extract_filename("attachment; filename=\"a\\\\b.txt\"").unwrap(), "a\\b.txt");
+        assert_eq!(extract_filename("attachment; filename=\"C:\\dir\\\\a\\\"b\\\"\"").unwrap(),
+                   "C:\\dir\\a\"b\"");
+        assert_eq!(extract_filename("attachment; filename=my file.txt").unwrap(), "my file.txt");
+        assert_eq!(extract_filename("attachment; filename=\"=?UTF-8?B?4oKs?= =?utf-8?Q?_rates=2Etxt?=\"").unwrap(),
+                   "\u{20ac} rates.txt");
+        assert_eq!(extract_filename("attachment; filename=\"a =?x?q?b?= c\"").unwrap(), "a =?x?q?b?= c");
+        assert_eq!(extract_filename("attachment; filename=\"=?utf-8?Q?100%_off=2E%41?=\"").unwrap(),
+                   "100% off.%41");
+        assert_eq!(extract_filename("attachment; name=\"a.txt\""), None);
+    }
+
+    #[test]
+    fn encoded_word_decoding_is_strict() {
+        assert_eq!(decode_base64("4oKs"), Some(vec![0xe2, 0x82, 0xac]));
+        assert_eq!(decode_base64("YQ=="), Some(b"a".to_vec()));
+        assert_eq!(decode_base64("YQ"), Some(b"a".to_vec()));
+        assert_eq!(decode_base64("YWI="), Some(b"ab".to_vec()));
+        for invalid in &["YR==", "YWJ=", "YQ=", "YQ===", "Y", "YQ==YQ==", "YWI=="] {
+            assert_eq!(decode_base64(invalid), None, "{:?}", invalid);
+        }
+        assert_eq!(decode_q("a_b=3D=c3=A4"), Some("a b=\u{e4}".as_bytes().to_vec()));
+        assert_eq!(decode_q("100%25"), Some(b"100%25".to_vec()));
+        assert_eq!(decode_q("a=4"), None);
+        assert_eq!(decode_q("a=xy"), None);
+        assert_eq!(decode_q("a=+4"), None);
+    }
+
+    #[test]
+    fn extract_filename_checks_limits() {
+        let header = "attachment; a=b; filename=c.txt";
+        let limits = Limits::new();
+        assert_eq!(extract_filename_with_limits(header, &limits), Ok(Some("c.txt".to_owned())));
+        let err = extract_filename_with_limits(header, &limits.with_max_params(1)).unwrap_err();
+        assert_eq!((err.kind, err.index), (LimitKind::ParamCount, 17));
+        let err = extract_filename_with_limits(header, &limits.with_max_param_value_length(4)).unwrap_err();
+        assert_eq!((err.kind, err.index), (LimitKind::ParamValueLength, 30));
+        let header = "attachment; a=((b)); filename=c (1).txt";
+        let err = extract_filename_with_limits(header, &limits.with_max_comment_depth(1)).unwrap_err();
+        assert_eq!((err.kind, err.index), (LimitKind::CommentDepth, 15));
+        assert_eq!(extract_filename(header), Some("c (1).txt".to_owned()));
+    }
+
+    #[test]
+    fn default_limits_are_checked() {
+        let mut header = String::from("attachment; filename=a.txt");
+        for idx in 0..DEFAULT_MAX_PARAMS {
+            header.push_str(&format!("; p{}=v", idx));
+        }
+        assert_eq!(extract_filename(&header), None);
+        assert_eq!(safe_filename(&header), None);
+        assert_eq!(safe_filename_with_limits(&header, &Limits::unlimited()), Ok(Some("a.txt".to_owned())));
+        assert_eq!(safe_filename_with_limits(&header, &Limits::new()).unwrap_err().kind, LimitKind::ParamCount);
+        let err = header.parse::<ContentDisposition>().unwrap_err();
+        assert_eq!(err.kind, DispositionErrorKind::LimitExceeded(LimitKind::ParamCount));
+    }
+
+    #[test]
+    fn sanitizing() {
+        let sanitizer = FilenameSanitizer::new();
+        assert_eq!(sanitizer.sanitize("C:\\Users\\me\\a.txt").unwrap(), "a.txt");
+        assert_eq!(sanitizer.sanitize("../../etc/passwd").unwrap(), "passwd");
+        assert_eq!(sanitizer.sanitize(".."), None);
+        assert_eq!(sanitizer.sanitize("a/.."), None);
+        assert_eq!(sanitizer.sanitize(" .hidden. "), Some("hidden".to_owned()));
+        assert_eq!(sanitizer.sanitize("a\u{0}b\r\nc\u{85}.txt").unwrap(), "abc.txt");
+        assert_eq!(sanitizer.sanitize("evil\u{202E}fdp.exe").unwrap(), "evilfdp.exe");
+        assert_eq!(sanitizer.sanitize("a\u{200B}b?c*.txt").unwrap(), "ab_c_.txt");
+        assert_eq!(sanitizer.sanitize("a\u{2215}b\u{a0}c").unwrap(), "a_b c");
+        assert_eq!(sanitizer.sanitize("nul.txt").unwrap(), "_nul.txt");
+        assert_eq!(sanitizer.sanitize("Com1").unwrap(), "_Com1");
+        assert_eq!(sanitizer.sanitize("console.txt").unwrap(), "console.txt");
+        assert_eq!(FilenameSanitizer::new().with_replacement('-').sanitize("a:b").unwrap(), "a-b");
+    }
+
+    #[test]
+    fn sanitizing_normalizes_to_nfc() {
+        let sanitizer = FilenameSanitizer::new();
+        assert_eq!(sanitizer.sanitize("e\u{301}.txt").unwrap(), "\u{e9}.txt");
+        assert_eq!(sanitizer.sanitize("\u{e9}.txt").unwrap(), "\u{e9}.txt");
+        // the zero-width space is removed before normalizing
+        assert_eq!(sanitizer.sanitize("a\u{30a}\u{200B}\u{301}").unwrap(), "\u{1fb}");
+    }
+
+    #[test]
+    fn truncation_keeps_extension() {
+        let sanitizer = FilenameSanitizer::new().with_max_length(10);
+        assert_eq!(sanitizer.sanitize("abcdefghijkl.pdf").unwrap(), "abcdef.pdf");
+        assert_eq!(sanitizer.sanitize("\u{e4}\u{e4}\u{e4}\u{e4}.pdf").unwrap(), "\u{e4}\u{e4}\u{e4}.pdf");
+        assert_eq!(sanitizer.sanitize("abcdefghijklmnop").unwrap(), "abcdefghij");
+        assert_eq!(sanitizer.sanitize("a.verylongextension").unwrap(), "a.verylong");
+        let name = "x".repeat(300) + ".txt";
+        let sanitized = FilenameSanitizer::new().sanitize(&name).unwrap();
+        assert_eq!(sanitized.len(), MAX_FILENAME_LENGTH);
+        assert!(sanitized.ends_with(".txt"));
+    }
+
+    #[test]
+    fn device_names_are_checked_after_truncation() {
+        let sanitizer = FilenameSanitizer::new().with_max_length(7);
+        assert_eq!(sanitizer.sanitize("CONxxxxx.pdf").unwrap(), "_CO.pdf");
+        assert_eq!(sanitizer.sanitize("nul.pdf").unwrap(), "_nu.pdf");
+        let sanitizer = FilenameSanitizer::new().with_max_length(3);
+        assert_eq!(sanitizer.sanitize("CONSOLE").unwrap(), "_CO");
+        assert_eq!(sanitizer.sanitize("AUXILIARY").unwrap(), "_AU");
+    }
+
+    fn parse(input: &str) -> Result<ContentDisposition, DispositionParseError> {
+        ContentDisposition::parse(input, DispositionSyntax::Http)
+    }
+
+    fn parse_err(input: &str, syntax: DispositionSyntax) -> (DispositionErrorKind, usize) {
+        let err = ContentDisposition::parse(input, syntax).unwrap_err();
+        (err.kind, err.index)
+    }
+
+    #[test]
+    fn parse_http() {
+        let cd = parse("Attachment; FileName=\"a \\\"b\\\".txt\"; filename*=utf-8'en'%C3%A4.txt; foo=bar").unwrap();
+        assert_eq!(cd.disposition_type, DispositionType::Attachment);
+        assert_eq!(cd.filename.as_ref().unwrap(), "a \"b\".txt");
+        assert_eq!(cd.filename_ext.as_ref().unwrap(), "\u{e4}.txt");
+        assert_eq!(cd.preferred_filename(), Some("\u{e4}.txt"));
+        assert_eq!(cd.params, vec![("foo".to_owned(), "bar".to_owned())]);
+
+        let cd: ContentDisposition = "form-data; name=field; filename=a.txt;".parse().unwrap();
+        assert_eq!(cd.disposition_type, DispositionType::FormData);
+        assert_eq!(cd.name.as_ref().unwrap(), "field");
+        assert_eq!(cd.preferred_filename(), Some("a.txt"));
+        assert_eq!(parse("x-custom").unwrap().disposition_type, DispositionType::Other("x-custom".to_owned()));
+
+        let cd = parse("attachment; filename = a.txt ;size\t=\t3").unwrap();
+        assert_eq!(cd.filename.as_ref().unwrap(), "a.txt");
+        assert_eq!(cd.size, Some(3));
+    }
+
+    #[test]
+    fn parse_mime() {
+        let input = "attachment; filename = \"gen\r\n eric.txt\";\r\n creation-date=\"Wed, 12 Feb 1997 16:29:51 -0500\"; size=1234; read-date=\"r\u{e9}\"";
+        let cd = ContentDisposition::parse(input, DispositionSyntax::Mime).unwrap();
+        assert_eq!(cd.filename.as_ref().unwrap(), "gen eric.txt");
+        assert_eq!(cd.creation_date.as_ref().unwrap(), "Wed, 12 Feb 1997 16:29:51 -0500");
+        assert_eq!(cd.read_date.as_ref().unwrap(), "r\u{e9}");
+        assert_eq!(cd.size, Some(1234));
+        assert_eq!(parse_err(input, DispositionSyntax::Http), (DispositionErrorKind::InvalidValue, 27));
+    }
+
+    #[test]
+    fn parse_mime_comments() {
+        use self::DispositionErrorKind::*;
+        let mime = DispositionSyntax::Mime;
+        let input = "attachment (some (nested\\)) comment);\r\n (x) filename=a.txt (\u{e4})";
+        let cd = ContentDisposition::parse(input, mime).unwrap();
+        assert_eq!(cd.filename.as_ref().unwrap(), "a.txt");
+        assert_eq!(parse_err("inline (a", mime), (InvalidComment, 7));
+        assert_eq!(parse_err("inline (a\u{1})", mime), (InvalidComment, 9));
+        assert_eq!(parse_err("inline (a)", DispositionSyntax::Http), (ExpectedSemicolon, 7));
+    }
+
+    #[test]
+    fn parse_with_limits() {
+        use self::DispositionErrorKind::*;
+        use limits::LimitKind::*;
+        let parse_err = |input: &str, limits: Limits| {
+            let err = ContentDisposition::parse_with_limits(input, DispositionSyntax::Mime, &limits)
+                .unwrap_err();
+            (err.kind, err.index)
+        };
+        let limits = Limits::new();
+        assert_eq!(parse_err(&"a".repeat(10_000), limits), (LimitExceeded(Length), 8192));
+        assert_eq!(parse_err("inline; a=b; b=c", limits.with_max_params(1)), (LimitExceeded(ParamCount), 13));
+        assert_eq!(parse_err("inline; abc=d", limits.with_max_param_name_length(2)),
+                   (LimitExceeded(ParamNameLength), 10));
+        assert_eq!(parse_err("inline; a=\"bcd\"", limits.with_max_param_value_length(4)),
+                   (LimitExceeded(ParamValueLength), 14));
+        assert_eq!(parse_err("inline (a (b (c)))", limits.with_max_comment_depth(2)),
+                   (LimitExceeded(CommentDepth), 13));
+        assert_eq!(parse_err("inline;\r\n a=b;\r\n c=d", limits.with_max_folds(1)),
+                   (LimitExceeded(Folds), 15));
+        let cd = ContentDisposition::parse_with_limits("inline; a=\"bcd\"", DispositionSyntax::Http, &limits).unwrap();
+        assert_eq!(cd.params, vec![("a".to_owned(), "bcd".to_owned())]);
+    }
+
+    #[test]
+    fn parse_many_params() {
+        let mut input = String::from("inline");
+        for idx in 0..30_000 {
+            input.push_str(&format!("; p{}=v", idx));
+        }
+        let parse = |input: &str| {
+            ContentDisposition::parse_with_limits(input, DispositionSyntax::Http, &Limits::unlimited())
+        };
+        assert_eq!(parse(&input).unwrap().params.len(), 30_000);
+        input.push_str("; p0=v");
+        assert_eq!(parse(&input).unwrap_err().kind, DispositionErrorKind::DuplicateParam);
+    }
+
+    #[test]
+    fn parse_errors() {
+        use self::DispositionErrorKind::*;
+        let http = DispositionSyntax::Http;
+        assert_eq!(parse_err("", http), (InvalidDispositionType, 0));
+        assert_eq!(parse_err("; filename=a", http), (InvalidDispositionType, 0));
+        assert_eq!(parse_err("inline filename=a", http), (ExpectedSemicolon, 7));
+        assert_eq!(parse_err("inline; =a", http), (InvalidParamName, 8));
+        assert_eq!(parse_err("inline; filename", http), (ExpectedEquals, 16));
+        assert_eq!(parse_err("inline; filename=", http), (InvalidValue, 17));
+        assert_eq!(parse_err("inline; filename=\"a\u{7}\"", http), (InvalidValue, 19));
+        assert_eq!(parse_err("inline; filename*=a.txt", http), (InvalidExtValue, 18));
+        assert_eq!(parse_err("inline; filename*=koi8-r''a", http), (InvalidExtValue, 18));
+        assert_eq!(parse_err("inline; a=b; A=c", http), (DuplicateParam, 13));
+        assert_eq!(parse_err("inline; size=-1", http), (InvalidSize, 13));
+        assert_eq!(parse_err("inline; size=+1", http), (InvalidSize, 13));
+        assert_eq!(parse_err("inline; size=\"\"", http), (InvalidSize, 13));
+    }
+
+    #[test]
+    fn build_picks_representation() {
+        let cd = ContentDisposition::new(DispositionType::Attachment)
+            .with_filename("a.txt")
+            .with_param("x", "a b")
+            .with_param("y", "\u{e4}")
+            .with_param("z*", "c");
+        assert_eq!(cd.to_header_value().unwrap(),
+                   "attachment; filename=a.txt; x=\"a b\"; y*=UTF-8''%C3%A4; z*=UTF-8''c");
+
+        let cd = ContentDisposition::new(DispositionType::Inline).with_filename("a\"\r\n.txt");
+        assert_eq!(cd.to_header_value().unwrap(),
+                   "inline; filename=\"a\\\"__.txt\"; filename*=UTF-8''a%22%0D%0A.txt");
+
+        let mut cd = ContentDisposition::new(DispositionType::Attachment);
+        cd.filename = Some("rates.txt".to_owned());
+        cd.filename_ext = Some("\u{20ac} rates.txt".to_owned());
+        assert_eq!(cd.to_header_value().unwrap(),
+                   "attachment; filename=rates.txt; filename*=UTF-8''%E2%82%AC%20rates.txt");
+
+        let cd = ContentDisposition::new(DispositionType::Attachment).with_name("\u{e4}");
+        assert_eq!(cd.to_header_value().unwrap(), "attachment; name*=UTF-8''%C3%A4");
+    }
+
+    #[test]
+    fn build_form_data_uses_utf8() {
+        let cd = ContentDisposition::new(DispositionType::FormData)
+            .with_name("f\u{e4}ld")
+            .with_filename("r\u{e9}sum\u{e9}.pdf");
+        assert_eq!(cd.to_header_value().unwrap(),
+                   "form-data; name=\"f\u{e4}ld\"; filename=\"r\u{e9}sum\u{e9}.pdf\"");
+    }
+
+    #[test]
+    fn build_form_data_never_uses_ext_values() {
+        let cd = ContentDisposition::new(DispositionType::FormData)
+            .with_name("a\"b")
+            .with_filename("x\ry\"z\\.txt");
+        assert_eq!(cd.to_header_value().unwrap(),
+                   "form-data; name=\"a%22b\"; filename=\"x%0Dy%22z\\.txt\"");
+        let mut cd = ContentDisposition::new(DispositionType::FormData).with_filename("a.txt");
+        cd.filename_ext = Some("\u{20ac}.txt".to_owned());
+        assert_eq!(cd.to_header_value().unwrap(), "form-data; filename=\"\u{20ac}.txt\"");
+        let cd = ContentDisposition::new(DispositionType::Attachment).with_filename("a\rb");
+        assert_eq!(cd.to_header_value().unwrap(), "attachment; filename=\"a_b\"; filename*=UTF-8''a%0Db");
+    }
+
+    #[test]
+    fn build_errors() {
+        let cd = ContentDisposition::new(DispositionType::Other("a b".to_owned()));
+        assert_eq!(cd.to_header_value(), Err(DispositionBuildError::InvalidDispositionType));
+        let cd = ContentDisposition::new(DispositionType::Inline).with_param("a=b", "c");
+        assert_eq!(cd.to_header_value(), Err(DispositionBuildError::InvalidParamName("a=b".to_owned())));
+        for &name in &["filename", "Filename*", "name", "size", "creation-date", "read-date*"] {
+            let cd = ContentDisposition::new(DispositionType::Inline).with_param(name, "c");
+            assert_eq!(cd.to_header_value(), Err(DispositionBuildError::InvalidParamName(name.to_owned())));
+        }
+        for &(first, second) in &[("x", "x"), ("x", "X*"), ("x*", "x"), ("x*", "x*")] {
+            let cd = ContentDisposition::new(DispositionType::Inline)
+                .with_param(first, "\u{e4}")
+                .with_param(second, "c");
+            assert_eq!(cd.to_header_value(), Err(DispositionBuildError::InvalidParamName(second.to_owned())));
+        }
+    }
+
+    #[test]
+    fn build_round_trips_extension_params() {
+        let cd = ContentDisposition::new(DispositionType::Attachment)
+            .with_name("\u{e4}")
+            .with_filename("a.txt")
+            .with_param("x", "a b")
+            .with_param("y", "\u{e4}")
+            .with_param("z*", "c");
+        let parsed = parse(&cd.to_header_value().unwrap()).unwrap();
+        assert_eq!(parsed.filename.unwrap(), "a.txt");
+        assert_eq!(parsed.params, vec![
+            ("name*".to_owned(), "\u{e4}".to_owned()),
+            ("x".to_owned(), "a b".to_owned()),
+            ("y*".to_owned(), "\u{e4}".to_owned()),
+            ("z*".to_owned(), "c".to_owned())
+        ]);
+    }
+
+    #[test]
+    fn build_never_emits_line_breaks() {
+        let cd = ContentDisposition::new(DispositionType::FormData)
+            .with_name("f")
+            .with_filename("a\r\nContent-Type: text/html\0.txt")
+            .with_param("x", "\u{e4}\n");
+        let header = cd.to_header_value().unwrap();
+        assert!(is_safe_for_emission(&header));
+        assert_eq!(header, "form-data; name=\"f\"; filename=\"a%0D%0AContent-Type: text/html%00.txt\"; \
+            x=\"\u{e4}%0A\"");
+        assert_eq!(cd.to_header_value_with_mode(EmissionMode::Refuse),
+                   Err(DispositionBuildError::UnsafeValue("filename".to_owned())));
+        let cd = ContentDisposition::new(DispositionType::Inline).with_param("x", "a\rb");
+        assert_eq!(cd.to_header_value_with_mode(EmissionMode::Refuse),
+                   Err(DispositionBuildError::UnsafeValue("x".to_owned())));
+    }
+
+    #[test]
+    fn round_trip() {
+        let cd = ContentDisposition::new(DispositionType::Attachment)
+            .with_filename("\u{20ac} \"rates\".txt")
+            .with_size(10)
+            .with_param("x", "(y)");
+        let mut expected = cd.clone();
+        expected.filename = Some("_ \"rates\".txt".to_owned());
+        expected.filename_ext = Some("\u{20ac} \"rates\".txt".to_owned());
+        let header = cd.to_header_value().unwrap();
+        assert_eq!(parse(&header).unwrap(), expected);
+        assert_eq!(ContentDisposition::parse(&header, DispositionSyntax::Mime).unwrap(), expected);
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_replacement_panics() {
+        let _ = FilenameSanitizer::new().with_replacement('/');
+    }
+}
@@ -0,0 +1,659 @@
+use std::error::{Error as StdError};
+use std::borrow::Cow;
+use std::fmt::{self, Display, Write};
+
+use qs::error::CoreError;
+
+use limits::{Limits, LimitedError};
+
+/// the line length limit (excluding CRLF) every line _should_ keep to (RFC 5322)
+pub const RECOMMENDED_LINE_LENGTH: usize = 78;
+
+/// the line length limit (excluding CRLF) every line _must_ keep to (RFC 5322)
+pub const MAX_LINE_LENGTH: usize = 998;
+
+/// error returned if a header field could not be written (folded)
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum FoldingError {
+    /// the field name is empty or contains chars other than printable us-ascii except `':'`
+    InvalidFieldName,
+    /// the field value contains a `'\r'` or `'\n'`, line breaks are only inserted by the writer
+    LineBreakInValue,
+    /// the field value contains a `'\0'`, which could truncate the field
+    NulInValue,
+    /// there is no way to fold the field so that all lines keep to the hard line length limit
+    LineTooLong,
+    /// writing to the output failed
+    WriteFailed
+}
+
+impl Display for FoldingError {
+    fn fmt(&self, fter: &mut fmt::Formatter) -> fmt::Result {
+        use self::FoldingError::*;
+        fter.write_str(match *self {
+            InvalidFieldName =>
+                "header field name is empty or contains invalid chars",
+            LineBreakInValue =>
+                "header field value contains a '\\r' or '\\n'",
+            NulInValue =>
+                "header field value contains a '\\0'",
+            LineTooLong =>
+                "header field can not be folded to keep to the line length limit",
+            WriteFailed =>
+                "writing the header field to the output failed"
+        })
+    }
+}
+
+impl StdError for FoldingError {}
+
+/// Writes header fields folding them where needed to keep to the line length limits.
+///
+/// Folding is done by inserting a `"\r\n"` in front of a existing ws char, this can be
+/// between tokens, inside of quoted strings or inside of comments. For structured fields
+/// (see `with_structured`) additionally a FWS (`"\r\n "`) is inserted after a `';'` or `','`
+/// which is not in a quoted-string or comment if there is no ws after it. This is not done
+/// by default, as it adds a `' '` to the unfolded value (e.g. `"a,b"` becomes `"a, b"`) which
+/// changes unstructured values (like a `Subject`).
+///
+/// The writer never folds in front of ws which is not followed by a non ws char, so it
+/// never produces a ws-only continuation line (which is only valid in the `obs-` grammar).
+/// It also never splits a quoted-pair, so any quoted-string in the output is still valid
+/// wrt. the `MimeParsing` impl (if it was valid before).
+///
+/// # Example
+///
+/// ```
+/// use media_type_impl_utils::folding::HeaderFolder;
+///
+/// let folder = HeaderFolder::with_line_length(20);
+/// let folded = folder.fold("Content-Type", "text/plain; charset=utf-8").unwrap();
+/// assert_eq!(folded, "Content-Type:\r\n text/plain;\r\n charset=utf-8");
+///
+/// let folder = folder.with_structured(true);
+/// let folded = folder.fold("Content-Type", "text/plain;charset=utf-8").unwrap();
+/// assert_eq!(folded, "Content-Type:\r\n text/plain;\r\n charset=utf-8");
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct HeaderFolder {
+    line_length: usize,
+    max_line_length: usize,
+    structured: bool
+}
+
+impl Default for HeaderFolder {
+    fn default() -> Self {
+        HeaderFolder {
+            line_length: RECOMMENDED_LINE_LENGTH,
+            max_line_length: MAX_LINE_LENGTH,
+            structured: false
+        }
+    }
+}
+
+impl HeaderFolder {
+
+    /// create a new HeaderFolder using `RECOMMENDED_LINE_LENGTH` and `MAX_LINE_LENGTH`
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// create a new HeaderFolder folding lines longer than `line_length`
+    ///
+    /// Lines which can not be folded to be shorter than `line_length` are still
+    /// accepted as long as they are not longer than `MAX_LINE_LENGTH`.
+    pub fn with_line_length(line_length: usize) -> Self {
+        HeaderFolder {
+            line_length,
+            ..Default::default()
+        }
+    }
+
+    /// returns it with folding after `';'` and `','` enabled (or disabled)
+    ///
+    /// Only use this for structured fields (like `Content-Type`) in which a `' '` can be
+    /// added after a `';'` or `','` without changing the meaning of the value.
+    pub fn with_structured(self, structured: bool) -> Self {
+        HeaderFolder { structured, ..self }
+    }
+
+    /// the line length the folder tries to keep to
+    pub fn line_length(&self) -> usize {
+        self.line_length
+    }
+
+    /// true if it folds after `';'` and `','`, see `with_structured`
+    pub fn is_structured(&self) -> bool {
+        self.structured
+    }
+
+    /// write the header field `name: value` into a new string folding it if needed
+    ///
+    /// The final `"\r\n"` ending the header field is _not_ written.
+    pub fn fold(&self, name: &str, value: &str) -> Result<String, FoldingError> {
+        let mut out = String::with_capacity(name.len() + value.len() + 8);
+        self.write(name, value, &mut out)?;
+        Ok(out)
+    }
+
+    /// write the header field `name: value` into `out` folding it if needed
+    ///
+    /// The final `"\r\n"` ending the header field is _not_ written. If a error
+    /// other than `WriteFailed` is returned nothing was written to `out`.
+    pub fn write<W: Write>(&self, name: &str, value: &str, out: &mut W)
+        -> Result<(), FoldingError>
+    {
+        if name.is_empty() || !name.bytes().all(|bch| bch != b':' && (b'!'..=b'~').contains(&bch)) {
+            return Err(FoldingError::InvalidFieldName);
+        }
+        if value.bytes().any(|bch| bch == b'\r' || bch == b'\n') {
+            return Err(FoldingError::LineBreakInValue);
+        }
+        if value.bytes().any(|bch| bch == b'\0') {
+            return Err(FoldingError::NulInValue);
+        }
+
+        // the ws after the `':'` is part of the value (so it can be folded in front of),
+        // a value without leading ws gets a `' '`
+        let value: Cow<str> =
+            if value.is_empty() || value.starts_with(' ') || value.starts_with('\t') {
+                Cow::Borrowed(value)
+            } else {
+                Cow::Owned(format!(" {}", value))
+            };
+
+        let segments = segments(&value, self.structured);
+        // true for each segment starting a new line
+        let mut line_starts = vec![false; segments.len()];
+        let mut line_len = name.len() + 1;
+        for (idx, &(start, end, fold)) in segments.iter().enumerate() {
+            let seg_len = end - start;
+            match fold {
+                Some(synth) if line_len + seg_len > self.line_length => {
+                    if line_len > self.max_line_length {
+                        return Err(FoldingError::LineTooLong);
+                    }
+                    line_starts[idx] = true;
+                    line_len = seg_len + if synth { 1 } else { 0 };
+                },
+                _ => line_len += seg_len
+            }
+        }
+        if line_len > self.max_line_length {
+            return Err(FoldingError::LineTooLong);
+        }
+
+        let res: fmt::Result = (|| {
+            out.write_str(name)?;
+            out.write_char(':')?;
+            for (&(start, end, fold), &line_start) in segments.iter().zip(line_starts.iter()) {
+                if line_start {
+                    out.write_str("\r\n")?;
+                    if fold == Some(true) {
+                        out.write_char(' ')?;
+                    }
+                }
+                out.write_str(&value[start..end])?;
+            }
+            Ok(())
+        })();
+        res.map_err(|_| FoldingError::WriteFailed)
+    }
+}
+
+/// splits the value into segments in front of which it can be folded
+///
+/// Each segment is returned as `(start, end, fold)` where fold is `None` if it
+/// can not be folded in front of the segment, `Some(false)` if it can be folded
+/// by inserting `"\r\n"` and `Some(true)` if it can be folded by inserting `"\r\n "`.
+fn segments(value: &str, structured: bool) -> Vec<(usize, usize, Option<bool>)> {
+    let mut segments = Vec::new();
+    let mut last = (0, None);
+    for (pos, synth) in fold_points(value, structured) {
+        if pos != last.0 {
+            segments.push((last.0, pos, last.1));
+        }
+        last = (pos, Some(synth));
+    }
+    if last.0 != value.len() {
+        segments.push((last.0, value.len(), last.1));
+    }
+    segments
+}
+
+/// returns all positions in value in front of which the value can be folded
+///
+/// A position with `true` means a `' '` has to be inserted after the `"\r\n"`, such
+/// positions (after a `';'` or `','`) are only returned for structured fields.
+fn fold_points(value: &str, structured: bool) -> Vec<(usize, bool)> {
+    let bytes = value.as_bytes();
+    let mut points = Vec::new();
+    let mut in_quoted_string = false;
+    let mut comment_depth = 0usize;
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let bch = bytes[idx];
+        match bch {
+            b'\\' if in_quoted_string || comment_depth > 0 => {
+                // never split a quoted-pair
+                idx += 2;
+                continue;
+            },
+            b'"' if comment_depth == 0 => {
+                in_quoted_string = !in_quoted_string;
+            },
+            b'(' if !in_quoted_string => {
+                comment_depth += 1;
+            },
+            b')' if !in_quoted_string && comment_depth > 0 => {
+                comment_depth -= 1;
+            },
+            b' ' | b'\t' => {
+                let ws_start = idx;
+                while idx < bytes.len() && (bytes[idx] == b' ' || bytes[idx] == b'\t') {
+                    idx += 1;
+                }
+                // folding in front of tailing ws would create a ws-only line
+                if idx < bytes.len() {
+                    points.push((ws_start, false));
+                }
+                continue;
+            },
+            b';' | b',' if structured && !in_quoted_string && comment_depth == 0 => {
+                let next = idx + 1;
+                if next < bytes.len() && bytes[next] != b' ' && bytes[next] != b'\t' {
+                    points.push((next, true));
+                }
+            },
+            _ => {}
+        }
+        idx += 1;
+    }
+    points
+}
+
+/// the way a `Unfold` iterator handles `'\r'` and `'\n'` which are not part of a FWS
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum UnfoldMode {
+    /// same rules as `FWSState`, a `'\r'` has to be followed by `'\n'` and a `'\n'`
+    /// has to be followed by `' '` or `'\t'`, anything else is a error
+    Strict,
+    /// `"\n"` followed by `' '` or `'\t'` is also treated as a FWS and any other
+    /// `'\r'`/`'\n'` is kept as part of the value (as it can be found with some
+    /// non standard compliant (obs) implementations)
+    Lenient
+}
+
+/// a iterator over the segments of a folded header field value
+///
+/// Each FWS (`"\r\n"` followed by `' '` or `'\t'`) splits the value into
+/// segments, the `"\r\n"` is not part of any segment but the ws after it
+/// is kept as the first char of the next segment. So concatenating all segments
+/// results in the unfolded value. Segments are sub-slices of the input, no
+/// copying is done.
+///
+/// In `UnfoldMode::Strict` a invalid `'\r'`/`'\n'` causes the iterator to return
+/// `CoreError::InvalidChar` with the index of the byte at which the input became
+/// invalid (like with `FWSState` this is e.g. the byte after a `'\r'` which is not
+/// a `'\n'`), after which it ends.
+///
+/// # Example
+///
+/// ```
+/// use media_type_impl_utils::folding::{Unfold, UnfoldMode};
+///
+/// let folded = b"text/plain;\r\n charset=utf-8";
+/// let segments = Unfold::new(folded, UnfoldMode::Strict)
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+/// assert_eq!(segments, vec![&b"text/plain;"[..], &b" charset=utf-8"[..]]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Unfold<'a> {
+    input: &'a [u8],
+    pos: usize,
+    mode: UnfoldMode,
+    failed: bool
+}
+
+impl<'a> Unfold<'a> {
+
+    /// create a new iterator over the unfolded segments of `input`
+    pub fn new(input: &'a [u8], mode: UnfoldMode) -> Self {
+        Unfold { input, pos: 0, mode, failed: false }
+    }
+
+    fn is_ws_at(&self, idx: usize) -> bool {
+        self.input.get(idx).map(|&bch| bch == b' ' || bch == b'\t').unwrap_or(false)
+    }
+}
+
+impl<'a> Iterator for Unfold<'a> {
+    type Item = Result<&'a [u8], (usize, CoreError)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let input = self.input;
+        while !self.failed && self.pos < input.len() {
+            let start = self.pos;
+            let mut idx = start;
+            // (end of segment, start of next segment)
+            let mut split = None;
+            while idx < input.len() {
+                match input[idx] {
+                    b'\r' => {
+                        if input.get(idx + 1) == Some(&b'\n') && self.is_ws_at(idx + 2) {
+                            split = Some((idx, idx + 2));
+                            break;
+                        } else if self.mode == UnfoldMode::Strict {
+                            self.failed = true;
+                            let err_idx =
+                                if input.get(idx + 1) == Some(&b'\n') { idx + 2 } else { idx + 1 };
+                            return Some(Err((err_idx, CoreError::InvalidChar)));
+                        }
+                    },
+                    b'\n' => {
+                        if self.mode == UnfoldMode::Strict {
+                            self.failed = true;
+                            return Some(Err((idx, CoreError::InvalidChar)));
+                        } else if self.is_ws_at(idx + 1) {
+                            split = Some((idx, idx + 1));
+                            break;
+                        }
+                    },
+                    _ => {}
+                }
+                idx += 1;
+            }
+
+            let (end, next_start) = split.unwrap_or((input.len(), input.len()));
+            self.pos = next_start;
+            if end > start {
+                return Some(Ok(&input[start..end]));
+            }
+        }
+        None
+    }
+}
+
+/// unfolds the folded header field value `input`
+///
+/// This is a shortcut for using `Unfold` and concatenating all segments,
+/// if the input is not folded no copying is done.
+///
+/// # Error
+///
+/// see `Unfold`
+pub fn unfold<'a>(input: &'a [u8], mode: UnfoldMode) -> Result<Cow<'a, [u8]>, (usize, CoreError)> {
+    let mut iter = Unfold::new(input, mode);
+    let first = match iter.next() {
+        Some(first) => first?,
+        None => return Ok(Cow::Borrowed(&input[0..0]))
+    };
+    let second = match iter.next() {
+        Some(second) => second?,
+        None => return Ok(Cow::Borrowed(first))
+    };
+    let mut out = Vec::with_capacity(input.len());
+    out.extend_from_slice(first);
+    out.extend_from_slice(second);
+    for segment in iter {
+        out.extend_from_slice(segment?);
+    }
+    Ok(Cow::Owned(out))
+}
+
+/// unfolds the folded header field value `input`, checking the length and number of folds
+///
+/// This is `Limits::check_header` followed by `unfold`, so the input is rejected before
+/// unfolding (and possible copying) it.
+///
+/// # Error
+///
+/// see `Limits::check_header` and `Unfold`
+pub fn unfold_with_limits<'a>(input: &'a [u8], mode: UnfoldMode, limits: &Limits)
+    -> Result<Cow<'a, [u8]>, LimitedError>
+{
+    limits.check_header(input)?;
+    Ok(unfold(input, mode)?)
+}
+
+#[cfg(test)]
+mod test {
+    use qs::{self, spec::GeneralQSSpec};
+    use quoted_string::{MimeParsing, NormalQuoting};
+    use super::*;
+
+    #[derive(Copy, Clone, Debug)]
+    struct MimeSpec;
+
+    impl GeneralQSSpec for MimeSpec {
+        type Quoting = NormalQuoting;
+        type Parsing = MimeParsing;
+    }
+
+    fn assert_well_formed(folded: &str, limit: usize) {
+        for (idx, line) in folded.split("\r\n").enumerate() {
+            assert!(line.len() <= limit, "line too long: {:?}", line);
+            if idx != 0 {
+                assert!(line.starts_with(' ') || line.starts_with('\t'), "{:?}", line);
+                assert!(line.bytes().any(|bch| bch != b' ' && bch != b'\t'), "ws-only line");
+            }
+        }
+    }
+
+    #[test]
+    fn short_fields_are_not_folded() {
+        let folded = HeaderFolder::new().fold("Content-Type", "text/plain; charset=utf-8").unwrap();
+        assert_eq!(folded, "Content-Type: text/plain; charset=utf-8");
+    }
+
+    #[test]
+    fn leading_ws_of_value_is_kept() {
+        let folded = HeaderFolder::new().fold("Subject", "  hy").unwrap();
+        assert_eq!(folded, "Subject:  hy");
+    }
+
+    #[test]
+    fn long_content_disposition_is_folded() {
+        let long_name = "a really long file name ".repeat(12);
+        let value = format!("attachment; filename=\"{}.pdf\"; size=1234", long_name.trim());
+        let folder = HeaderFolder::new();
+        let folded = folder.fold("Content-Disposition", &value).unwrap();
+
+        assert_well_formed(&folded, RECOMMENDED_LINE_LENGTH);
+        assert_eq!(folded.replace("\r\n", ""), format!("Content-Disposition: {}", value));
+
+        let qs_start = folded.find('"').unwrap();
+        let parsed = qs::parse::<MimeSpec>(&folded[qs_start..]).unwrap();
+        assert!(parsed.quoted_string.contains("\r\n "));
+    }
+
+    #[test]
+    fn fws_is_inserted_after_separators() {
+        let folder = HeaderFolder::with_line_length(20).with_structured(true);
+        let folded = folder.fold("Content-Type", "text/plain;charset=utf-8;format=flowed").unwrap();
+        assert_eq!(folded, "Content-Type:\r\n text/plain;\r\n charset=utf-8;\r\n format=flowed");
+    }
+
+    #[test]
+    fn unstructured_fields_are_only_folded_at_existing_ws() {
+        let folder = HeaderFolder::with_line_length(10);
+        let folded = folder.fold("Subject", "a,b;cdefghijk lm").unwrap();
+        assert_eq!(folded, "Subject:\r\n a,b;cdefghijk\r\n lm");
+        assert_eq!(folded.replace("\r\n", ""), "Subject: a,b;cdefghijk lm");
+    }
+
+    #[test]
+    fn does_not_fold_inside_quoted_pair() {
+        let folder = HeaderFolder::with_line_length(10);
+        let folded = folder.fold("X", "\"abcdefgh\\ ijklmn\"").unwrap();
+        assert_eq!(folded, "X:\r\n \"abcdefgh\\ ijklmn\"");
+    }
+
+    #[test]
+    fn does_not_fold_in_front_of_tailing_ws() {
+        let folder = HeaderFolder::with_line_length(10);
+        let folded = folder.fold("X", "abcdefghijk      ").unwrap();
+        assert_eq!(folded, "X:\r\n abcdefghijk      ");
+    }
+
+    #[test]
+    fn folds_inside_comments_but_not_after_separators_in_them() {
+        let folder = HeaderFolder::with_line_length(16);
+        let folded = folder.fold("X", "a/b (some;comment text)").unwrap();
+        assert_eq!(folded, "X: a/b\r\n (some;comment\r\n text)");
+    }
+
+    #[test]
+    fn line_breaks_in_value_are_rejected() {
+        let res = HeaderFolder::new().fold("X", "a\r\n b");
+        assert_eq!(res, Err(FoldingError::LineBreakInValue));
+        let res = HeaderFolder::new().fold("X", "a\0b");
+        assert_eq!(res, Err(FoldingError::NulInValue));
+    }
+
+    #[test]
+    fn invalid_field_names_are_rejected() {
+        assert_eq!(HeaderFolder::new().fold("", "a"), Err(FoldingError::InvalidFieldName));
+        assert_eq!(HeaderFolder::new().fold("X:Y", "a"), Err(FoldingError::InvalidFieldName));
+        assert_eq!(HeaderFolder::new().fold("X Y", "a"), Err(FoldingError::InvalidFieldName));
+    }
+
+    #[test]
+    fn unfoldable_lines_over_the_hard_limit_fail() {
+        let value = "a".repeat(MAX_LINE_LENGTH);
+        let res = HeaderFolder::new().fold("X", &value);
+        assert_eq!(res, Err(FoldingError::LineTooLong));
+    }
+
+    #[test]
+    fn unfoldable_lines_under_the_hard_limit_are_kept() {
+        let value = "a".repeat(100);
+        let folded = HeaderFolder::new().fold("X", &value).unwrap();
+        assert_eq!(folded, format!("X:\r\n {}", value));
+    }
+
+    mod unfold {
+        use std::borrow::Cow;
+        use qs::error::CoreError;
+        use limits::{Limits, LimitKind, LimitedError};
+        use super::super::{Unfold, UnfoldMode, unfold, unfold_with_limits};
+
+        fn segments(input: &[u8], mode: UnfoldMode) -> Result<Vec<&[u8]>, (usize, CoreError)> {
+            Unfold::new(input, mode).collect()
+        }
+
+        #[test]
+        fn unfolded_input_is_one_segment() {
+            let input = b"text/plain; charset=utf-8";
+            let res = segments(input, UnfoldMode::Strict).unwrap();
+            assert_eq!(res, vec![&input[..]]);
+        }
+
+        #[test]
+        fn empty_input_has_no_segments() {
+            let res = segments(b"", UnfoldMode::Strict).unwrap();
+            assert!(res.is_empty());
+        }
+
+        #[test]
+        fn crlf_is_dropped_ws_is_kept() {
+            let res = segments(b"a\r\n b\r\n\t\tc", UnfoldMode::Strict).unwrap();
+            assert_eq!(res, vec![&b"a"[..], &b" b"[..], &b"\t\tc"[..]]);
+        }
+
+        #[test]
+        fn segments_are_zero_copy() {
+            let input = b"abc\r\n def";
+            let res = segments(input, UnfoldMode::Strict).unwrap();
+            assert_eq!(res[0].as_ptr(), input.as_ptr());
+            assert_eq!(res[1].as_ptr(), input[5..].as_ptr());
+        }
+
+        #[test]
+        fn leading_fws_does_not_produce_empty_segment() {
+            let res = segments(b"\r\n a", UnfoldMode::Strict).unwrap();
+            assert_eq!(res, vec![&b" a"[..]]);
+        }
+
+        #[test]
+        fn strict_rejects_bare_cr() {
+            let res = segments(b"ab\rc", UnfoldMode::Strict);
+            assert_eq!(res, Err((3, CoreError::InvalidChar)));
+        }
+
+        #[test]
+        fn strict_rejects_bare_lf() {
+            let res = segments(b"ab\n c", UnfoldMode::Strict);
+            assert_eq!(res, Err((2, CoreError::InvalidChar)));
+        }
+
+        #[test]
+        fn strict_rejects_crlf_without_ws() {
+            let res = segments(b"ab\r\nc", UnfoldMode::Strict);
+            assert_eq!(res, Err((4, CoreError::InvalidChar)));
+            let res = segments(b"ab\r\n", UnfoldMode::Strict);
+            assert_eq!(res, Err((4, CoreError::InvalidChar)));
+        }
+
+        #[test]
+        fn strict_ends_after_error() {
+            let mut iter = Unfold::new(b"a\r\n b\rc\r\n d", UnfoldMode::Strict);
+            assert_eq!(iter.next(), Some(Ok(&b"a"[..])));
+            assert_eq!(iter.next(), Some(Err((6, CoreError::InvalidChar))));
+            assert_eq!(iter.next(), None);
+        }
+
+        #[test]
+        fn lenient_unfolds_lf_ws() {
+            let res = segments(b"a\n b\r\n c", UnfoldMode::Lenient).unwrap();
+            assert_eq!(res, vec![&b"a"[..], &b" b"[..], &b" c"[..]]);
+        }
+
+        #[test]
+        fn lenient_keeps_bare_cr_and_lf() {
+            let res = segments(b"a\rb\nc\r\nd\r\n", UnfoldMode::Lenient).unwrap();
+            assert_eq!(res, vec![&b"a\rb\nc\r\nd\r\n"[..]]);
+        }
+
+        #[test]
+        fn unfold_borrows_if_possible() {
+            let res = unfold(b"abc", UnfoldMode::Strict).unwrap();
+            assert_eq!(res, Cow::Borrowed(&b"abc"[..]));
+            let res = unfold(b"\r\n abc", UnfoldMode::Strict).unwrap();
+            assert_eq!(res, Cow::Borrowed(&b" abc"[..]));
+        }
+
+        #[test]
+        fn unfold_concatenates_segments() {
+            let res = unfold(b"a\r\n b\r\n c", UnfoldMode::Strict).unwrap();
+            let expected: Cow<[u8]> = Cow::Owned(b"a b c".to_vec());
+            assert_eq!(res, expected);
+        }
+
+        #[test]
+        fn unfold_reverses_folding() {
+            use super::super::HeaderFolder;
+            let value = "attachment; filename=\"some file name which is quite long.txt\"";
+            let folded = HeaderFolder::with_line_length(30).fold("X", value).unwrap();
+            let res = unfold(folded.as_bytes(), UnfoldMode::Strict).unwrap();
+            assert_eq!(&*res, format!("X: {}", value).as_bytes());
+        }
+
+        #[test]
+        fn unfold_with_limits_checks_folds_first() {
+            let limits = Limits::new().with_max_folds(1);
+            let res = unfold_with_limits(b"a\r\n b", UnfoldMode::Strict, &limits).unwrap();
+            assert_eq!(&*res, b"a b");
+            match unfold_with_limits(b"a\r\n b\r\n c\r", UnfoldMode::Strict, &limits) {
+                Err(LimitedError::Limit(err)) => {
+                    assert_eq!(err.kind, LimitKind::Folds);
+                    assert_eq!(err.index, 6);
+                },
+                other => panic!("unexpected result: {:?}", other)
+            }
+            assert_eq!(unfold_with_limits(b"a\rb", UnfoldMode::Strict, &limits),
+                       Err(LimitedError::Syntax(2, CoreError::InvalidChar)));
+        }
+    }
+}
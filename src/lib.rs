@@ -11,6 +11,29 @@
 //! - `lookup_table`: provides a `lut` lookup table for bytes/us-ascii chars used in context of
 //!   media-type parsing.
 //!
+//! - `folding`: provides a writer for header fields which folds them to keep to the line
+//!   length limits of the mime grammar.
+//!
+//! - `char_class`: provides a way to define custom character class tables (e.g. by combining
+//!   `lookup_tables` classes) which can be used with generic validators and quoting classifiers.
+//!
+//! - `scan`: provides fast (SSSE3/AVX2 on x86 if available) functions to find the end of
+//!   e.g. token or qtext runs.
+//!
+//! - `media_type_name`: provides a validator for whole media type names (`type/subtype`)
+//!   wrt. the registration rules of RFC6838, reporting which rule was violated.
+//!
+//! - `validator`: provides combinators (length limits, first/last char, ...) to build
+//!   `WithoutQuotingValidator`s which can explain why they failed.
+//!
+//! - `content_disposition`: provides a parser and builder for Content-Disposition headers
+//!   as well as a way to extract a safe filename from them.
+//!
+//! - `multipart`: provides a parser and writer for `multipart/*` bodies (e.g. `multipart/form-data`).
+//!
+//! - `limits`: provides configurable limits (length, number of parameters, ...) used by the
+//!   parsers to protect against hostile input.
+//!
 //! **Note: Currently is crate is rather unstable. It will still keep to
 //!  semver but changing to a newer (braking) version might induce large
 //!  api changes. Sill bug-fixes to older versions can be done if requested
@@ -23,10 +46,28 @@
 extern crate lut;
 extern crate quoted_string as qs;
 extern crate percent_encoding as penc;
+extern crate unicode_normalization;
 
 /// lut lookup tables for parsing media types
 pub mod lookup_tables;
 /// impl of traits from the quoted-string crate for parsing media types
 pub mod quoted_string;
 /// impl of EncodingSet's for encoding parameter values if needed
-pub mod percent_encoding;
\ No newline at end of file
+pub mod percent_encoding;
+/// folding of header fields (FWS) wrt. the mime line length limits
+pub mod folding;
+/// user defined character class tables
+#[macro_use]
+pub mod char_class;
+/// bulk scanning for runs of chars of a class (e.g. tokens)
+pub mod scan;
+/// validation of media type names wrt. RFC6838
+pub mod media_type_name;
+/// composable `WithoutQuotingValidator` combinators
+pub mod validator;
+/// parsing/building Content-Disposition headers and filename extraction
+pub mod content_disposition;
+/// parsing and writing of multipart bodies
+pub mod multipart;
+/// limits for parsing hostile input
+pub mod limits;
\ No newline at end of file
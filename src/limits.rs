@@ -0,0 +1,344 @@
+use std::error::{Error as StdError};
+use std::fmt::{self, Display};
+
+use qs::error::CoreError;
+
+/// the default limit for the length (in bytes) of a header value
+pub const DEFAULT_MAX_LENGTH: usize = 8 * 1024;
+
+/// the default limit for the number of parameters
+pub const DEFAULT_MAX_PARAMS: usize = 64;
+
+/// the default limit for the length (in bytes) of a parameter name
+pub const DEFAULT_MAX_PARAM_NAME_LENGTH: usize = 127;
+
+/// the default limit for the length (in bytes) of a (raw) parameter value
+pub const DEFAULT_MAX_PARAM_VALUE_LENGTH: usize = 4 * 1024;
+
+/// the default limit for how deep comments can be nested
+pub const DEFAULT_MAX_COMMENT_DEPTH: usize = 8;
+
+/// the default limit for the number of folds (line breaks) in a header value
+pub const DEFAULT_MAX_FOLDS: usize = 128;
+
+/// the kind of a limit, see `Limits`
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum LimitKind {
+    /// the length (in bytes) of the whole input
+    Length,
+    /// the number of parameters
+    ParamCount,
+    /// the length (in bytes) of a parameter name
+    ParamNameLength,
+    /// the length (in bytes) of a parameter value as it appears in the input
+    ParamValueLength,
+    /// how deep comments are nested
+    CommentDepth,
+    /// the number of folds, i.e. line breaks (`'\n'`) in the input
+    Folds
+}
+
+impl LimitKind {
+    pub(crate) fn description(&self) -> &'static str {
+        use self::LimitKind::*;
+        match *self {
+            Length => "input is too long",
+            ParamCount => "too many parameters",
+            ParamNameLength => "parameter name is too long",
+            ParamValueLength => "parameter value is too long",
+            CommentDepth => "comments are nested too deep",
+            Folds => "input is folded too often"
+        }
+    }
+}
+
+/// error returned if the input exceeds one of the `Limits`
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct LimitExceeded {
+    /// the limit which was exceeded
+    pub kind: LimitKind,
+    /// the value of the limit
+    pub limit: usize,
+    /// the (byte) index in the input at which the limit was exceeded
+    pub index: usize
+}
+
+impl Display for LimitExceeded {
+    fn fmt(&self, fter: &mut fmt::Formatter) -> fmt::Result {
+        write!(fter, "{} (limit: {}) at index {}", self.kind.description(), self.limit, self.index)
+    }
+}
+
+impl StdError for LimitExceeded {}
+
+/// error returned by parsers with limits which have no error type of their own
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum LimitedError {
+    /// a limit was exceeded
+    Limit(LimitExceeded),
+    /// the input is not valid, with the (byte) index at which this was found
+    Syntax(usize, CoreError)
+}
+
+impl Display for LimitedError {
+    fn fmt(&self, fter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LimitedError::Limit(ref err) => Display::fmt(err, fter),
+            LimitedError::Syntax(index, ref err) => write!(fter, "{} at index {}", err, index)
+        }
+    }
+}
+
+impl StdError for LimitedError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match *self {
+            LimitedError::Limit(ref err) => Some(err),
+            LimitedError::Syntax(_, ref err) => Some(err)
+        }
+    }
+}
+
+impl From<LimitExceeded> for LimitedError {
+    fn from(err: LimitExceeded) -> Self {
+        LimitedError::Limit(err)
+    }
+}
+
+impl From<(usize, CoreError)> for LimitedError {
+    fn from((index, err): (usize, CoreError)) -> Self {
+        LimitedError::Syntax(index, err)
+    }
+}
+
+/// limits for parsing (possibly hostile) input
+///
+/// Parsers check the limits as early as possible, e.g. the length before parsing anything
+/// so that a huge input is rejected without looking at it. `Limits::new()` uses the
+/// `DEFAULT_*` limits which are generous for any legit header value, `Limits::unlimited()`
+/// disables all limits. Parsers without a `*_with_limits` suffix use `Limits::new()`, except
+/// `unfold` and `QuotedStringScanner::feed` which are unlimited as their errors can not
+/// represent a exceeded limit.
+///
+/// # Example
+///
+/// ```
+/// use media_type_impl_utils::limits::{Limits, LimitKind};
+///
+/// let limits = Limits::new().with_max_folds(1);
+/// assert!(limits.check_header(b"text/plain;\r\n charset=utf-8").is_ok());
+/// let err = limits.check_header(b"a;\r\n b=c;\r\n d=e").unwrap_err();
+/// assert_eq!(err.kind, LimitKind::Folds);
+/// assert_eq!(err.index, 10);
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Limits {
+    max_length: usize,
+    max_params: usize,
+    max_param_name_length: usize,
+    max_param_value_length: usize,
+    max_comment_depth: usize,
+    max_folds: usize
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_length: DEFAULT_MAX_LENGTH,
+            max_params: DEFAULT_MAX_PARAMS,
+            max_param_name_length: DEFAULT_MAX_PARAM_NAME_LENGTH,
+            max_param_value_length: DEFAULT_MAX_PARAM_VALUE_LENGTH,
+            max_comment_depth: DEFAULT_MAX_COMMENT_DEPTH,
+            max_folds: DEFAULT_MAX_FOLDS
+        }
+    }
+}
+
+impl Limits {
+
+    /// create new limits using the `DEFAULT_*` limits
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// create limits which never are exceeded
+    pub fn unlimited() -> Self {
+        Limits {
+            max_length: usize::MAX,
+            max_params: usize::MAX,
+            max_param_name_length: usize::MAX,
+            max_param_value_length: usize::MAX,
+            max_comment_depth: usize::MAX,
+            max_folds: usize::MAX
+        }
+    }
+
+    /// returns it with the limit for the length of the input set
+    pub fn with_max_length(self, max_length: usize) -> Self {
+        Limits { max_length, ..self }
+    }
+
+    /// returns it with the limit for the number of parameters set
+    pub fn with_max_params(self, max_params: usize) -> Self {
+        Limits { max_params, ..self }
+    }
+
+    /// returns it with the limit for the length of parameter names set
+    pub fn with_max_param_name_length(self, max_param_name_length: usize) -> Self {
+        Limits { max_param_name_length, ..self }
+    }
+
+    /// returns it with the limit for the length of parameter values set
+    pub fn with_max_param_value_length(self, max_param_value_length: usize) -> Self {
+        Limits { max_param_value_length, ..self }
+    }
+
+    /// returns it with the limit for the comment nesting depth set
+    pub fn with_max_comment_depth(self, max_comment_depth: usize) -> Self {
+        Limits { max_comment_depth, ..self }
+    }
+
+    /// returns it with the limit for the number of folds set
+    pub fn with_max_folds(self, max_folds: usize) -> Self {
+        Limits { max_folds, ..self }
+    }
+
+    /// the value of the limit of the given kind
+    pub fn limit(&self, kind: LimitKind) -> usize {
+        use self::LimitKind::*;
+        match kind {
+            Length => self.max_length,
+            ParamCount => self.max_params,
+            ParamNameLength => self.max_param_name_length,
+            ParamValueLength => self.max_param_value_length,
+            CommentDepth => self.max_comment_depth,
+            Folds => self.max_folds
+        }
+    }
+
+    /// checks if `value` is within the limit of the given kind
+    ///
+    /// # Error
+    ///
+    /// if `value` is larger than the limit, `index` is used as index of the error
+    pub fn check(&self, kind: LimitKind, value: usize, index: usize) -> Result<(), LimitExceeded> {
+        let limit = self.limit(kind);
+        if value > limit {
+            Err(LimitExceeded { kind, limit, index })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// checks the length `len` of a item (e.g. a parameter name) starting at `start`
+    ///
+    /// # Error
+    ///
+    /// if `len` is larger than the limit, the index is the one of the first byte exceeding it
+    pub fn check_length(&self, kind: LimitKind, start: usize, len: usize) -> Result<(), LimitExceeded> {
+        self.check(kind, len, start.saturating_add(self.limit(kind)))
+    }
+
+    /// checks the length and number of folds of a (whole) header value
+    ///
+    /// Every `'\n'` is counted as fold, as this is cheap it can be used in front of any
+    /// parser (e.g. `quoted_string::parse`) without limits of it's own.
+    ///
+    /// # Error
+    ///
+    /// if the input is longer than the length limit (at the index of the limit) or has
+    /// more folds than allowed (at the index of the first `'\n'` too much)
+    pub fn check_header(&self, input: &[u8]) -> Result<(), LimitExceeded> {
+        self.check(LimitKind::Length, input.len(), self.max_length)?;
+        if self.max_folds < input.len() {
+            let mut folds = 0;
+            for (idx, &bch) in input.iter().enumerate() {
+                if bch == b'\n' {
+                    folds += 1;
+                    self.check(LimitKind::Folds, folds, idx)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// checks how deep comments (`'('`, `')'`) are nested in a (whole) header value
+    ///
+    /// `'('` in quoted-strings and quoted-pairs are not counted, otherwise this does not
+    /// validate the input (unbalanced `')'` are ignored), so it can be used in front of any
+    /// parser, including lenient ones which take comments to be part of a value.
+    ///
+    /// # Error
+    ///
+    /// if comments are nested deeper than allowed, at the index of the `'('` too much
+    pub fn check_comment_depth(&self, input: &[u8]) -> Result<(), LimitExceeded> {
+        if self.max_comment_depth >= input.len() {
+            return Ok(());
+        }
+        let mut depth = 0usize;
+        let mut in_quoted = false;
+        let mut escaped = false;
+        for (idx, &bch) in input.iter().enumerate() {
+            if escaped {
+                escaped = false;
+            } else if bch == b'\\' {
+                escaped = true;
+            } else if in_quoted {
+                in_quoted = bch != b'"';
+            } else if bch == b'"' && depth == 0 {
+                in_quoted = true;
+            } else if bch == b'(' {
+                depth += 1;
+                self.check(LimitKind::CommentDepth, depth, idx)?;
+            } else if bch == b')' {
+                depth = depth.saturating_sub(1);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check() {
+        let limits = Limits::new().with_max_params(2);
+        assert_eq!(limits.check(LimitKind::ParamCount, 2, 10), Ok(()));
+        assert_eq!(limits.check(LimitKind::ParamCount, 3, 10),
+                   Err(LimitExceeded { kind: LimitKind::ParamCount, limit: 2, index: 10 }));
+        assert_eq!(Limits::unlimited().check(LimitKind::Length, usize::MAX, 0), Ok(()));
+        assert_eq!(limits.check_length(LimitKind::ParamCount, 5, 3).unwrap_err().index, 7);
+    }
+
+    #[test]
+    fn check_header_length() {
+        let limits = Limits::new().with_max_length(3);
+        assert_eq!(limits.check_header(b"abc"), Ok(()));
+        assert_eq!(limits.check_header(b"abcd").unwrap_err().index, 3);
+        let huge = vec![b'a'; DEFAULT_MAX_LENGTH + 1];
+        assert_eq!(Limits::new().check_header(&huge).unwrap_err().kind, LimitKind::Length);
+    }
+
+    #[test]
+    fn check_header_folds() {
+        let limits = Limits::unlimited().with_max_folds(0);
+        assert_eq!(limits.check_header(b"a b"), Ok(()));
+        assert_eq!(limits.check_header(b"a\r\n b"),
+                   Err(LimitExceeded { kind: LimitKind::Folds, limit: 0, index: 2 }));
+    }
+
+    #[test]
+    fn check_comment_depth() {
+        let limits = Limits::new().with_max_comment_depth(1);
+        assert_eq!(limits.check_comment_depth(b"a (b) (c); d=\"((\" (\\(e)"), Ok(()));
+        assert_eq!(limits.check_comment_depth(b"a (b (c))"),
+                   Err(LimitExceeded { kind: LimitKind::CommentDepth, limit: 1, index: 5 }));
+        assert_eq!(limits.check_comment_depth(b"a (\"b (c)"), Err(LimitExceeded {
+            kind: LimitKind::CommentDepth, limit: 1, index: 6
+        }));
+        let nested = vec![b'('; 10_000];
+        assert_eq!(Limits::new().check_comment_depth(&nested).unwrap_err().index,
+                   DEFAULT_MAX_COMMENT_DEPTH);
+    }
+}
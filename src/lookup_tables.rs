@@ -35,14 +35,92 @@ new_table! {
         ///
         /// Note: while using a lookup just for to determine if it is `' '` or `'\t'` makes little sense
         /// using it with a already looked up value or in combination with others can make sense
-        Ws = Ws
+        Ws = Ws,
+
+        /// AttrChar characters allowed in a percent encoded parameter value given the
+        /// grammar from RFC8187 (http), i.e. `HttpToken` except `'*'`, `'\''` and `'%'`
+        AttrChar = AC,
+
+        /// AttributeChar characters allowed in a percent encoded parameter value given the
+        /// grammar from RFC2231 (mime), i.e. `Token` except `'*'`, `'\''` and `'%'`
+        AttributeChar = AT,
+
+        /// UriUnreserved characters, i.e. `unreserved` from RFC3986 (uri)
+        UriUnreserved = UR,
+
+        /// UriSubDelims characters, i.e. `sub-delims` from RFC3986 (uri)
+        UriSubDelims = SD,
+
+        /// UriGenDelims characters, i.e. `gen-delims` from RFC3986 (uri)
+        UriGenDelims = GD,
+
+        /// UriPChar characters allowed in a uri path segment, i.e. `pchar` from RFC3986 (uri)
+        ///
+        /// Note: this includes `'%'` as the start of a `pct-encoded` sequence, that it is followed
+        /// by two hex digits has to be checked separately
+        UriPChar = PC,
+
+        /// UriQuery characters allowed in the query or fragment of a uri, i.e. the chars of
+        /// `query`/`fragment` from RFC3986 (uri) which are `pchar`, `'/'` and `'?'`
+        ///
+        /// Note: this includes `'%'` as the start of a `pct-encoded` sequence, that it is followed
+        /// by two hex digits has to be checked separately
+        UriQuery = UQ,
+
+        /// SfTokenStart characters allowed as first char of a `sf-token` from RFC8941
+        /// (structured fields), i.e. `ALPHA` and `'*'`
+        SfTokenStart = SS,
+
+        /// SfToken characters allowed in a `sf-token` (except as first char) from RFC8941
+        /// (structured fields), i.e. `HttpToken`, `':'` and `'/'`
+        SfToken = ST,
+
+        /// SfKeyStart characters allowed as first char of a `key` from RFC8941
+        /// (structured fields), i.e. `lcalpha` and `'*'`
+        SfKeyStart = KS,
+
+        /// SfKey characters allowed in a `key` (except as first char) from RFC8941
+        /// (structured fields), i.e. `lcalpha`, `DIGIT`, `'_'`, `'-'`, `'.'` and `'*'`
+        SfKey = SK,
+
+        /// SfStringChar characters which can appear unescaped in a `sf-string` from RFC8941
+        /// (structured fields), i.e. `' '` and `VChar` except `'"'` and `'\\'`
+        ///
+        /// Note: in difference to `QTextWs` this does not include `'\t'`
+        SfStringChar = SC,
+
+        /// BChars characters allowed in a multipart boundary given the grammar from
+        /// RFC2046 (mime), i.e. `DIGIT`, `ALPHA`, `'\''`, `'('`, `')'`, `'+'`, `'_'`, `','`,
+        /// `'-'`, `'.'`, `'/'`, `':'`, `'='`, `'?'` and `' '`
+        ///
+        /// Note: a boundary can not end with `' '`
+        BChars = BC,
+
+        /// BCharsNoSpace characters allowed as last char of a multipart boundary given
+        /// the grammar from RFC2046 (mime), i.e. `BChars` except `' '`
+        BCharsNoSpace = BN,
+
+        /// CookieOctet characters allowed in a cookie value given the grammar from RFC6265
+        /// (`cookie-octet`), i.e. `VChar` except `'"'`, `','`, `';'` and `'\\'`
+        CookieOctet = CO,
+
+        /// AText characters allowed in a atom given the grammar from RFC5322 (mail),
+        /// i.e. `ALPHA`, `DIGIT` and ``"!#$%&'*+-/=?^_`{|}~"``
+        AText = ATX,
+
+        /// Specials characters given the grammar from RFC5322 (mail), i.e. ``"()<>[]:;@\,."``
+        /// and `'"'`, together with `AText` this are all `VChar`s
+        Specials = SPC
     }
 
     /// MediaTypeChars is a lookup table for a number of character classes relevant when parsing media types
     ///
     /// This are mainly:
     /// `CText`, `ObsNoWsCtl`, `HttpToken`, `Token`, `RestrictedToken`,
-    /// `QText`, `DQuotesOrEscape` and `Ws`
+    /// `QText`, `DQuotesOrEscape`, `Ws`, `AttrChar`, `AttributeChar`,
+    /// `UriUnreserved`, `UriSubDelims`, `UriGenDelims`, `UriPChar`, `UriQuery`,
+    /// `SfTokenStart`, `SfToken`, `SfKeyStart`, `SfKey`, `SfStringChar`,
+    /// `BChars`, `BCharsNoSpace`, `CookieOctet`, `AText` and `Specials`
     ///
     /// The classes `HttpToken`, `Token` and `RestrictedToken` are needed for the different specifications
     /// of a "token" in Http, Mime and for IANA registry compatible tokens.
@@ -56,48 +134,164 @@ new_table! {
     /// used for themself, but they do make sense if they are combined with others or used on
     /// with lookup result already available.
     ///
+    /// The classes `AttrChar` and `AttributeChar` are needed for percent encoding parameter
+    /// values (RFC8187 in Http, RFC2231 in Mime) as they differ from the token classes.
+    ///
+    /// The `Uri*` classes are needed for validating uris in parameter values (e.g. `profile=`)
+    /// and for percent encoding uri components.
+    ///
+    /// The `Sf*` classes are needed for structured field values (RFC8941) in Http, which have
+    /// their own grammar for tokens, keys and strings.
+    ///
+    /// The `BChars*` classes are needed for validating multipart boundaries (RFC2046).
+    ///
+    /// The `CookieOctet` class is needed for cookie values (RFC6265), cookie names are `HttpToken`s.
+    ///
+    /// The `AText` and `Specials` classes are needed for atoms and dot-atoms in mail headers (RFC5322).
+    ///
+    /// The table uses `u32` cells so that there is room for up to 32 classes. Checking a
+    /// class is a single indexed load and a mask independent of the cell type, so neither the
+    /// wider cells nor adding classes make looking up any class slower (see the
+    /// `lookup_table` benchmark which compares it with a table using `u8` cells).
+    ///
     pub struct MediaTypeChars {
-        static data: [u8; 256] = [
-            //0x00 + 0/4/8/C
-            -,               NC,              NC,              NC,
-            NC,              NC,              NC,              NC,
-            NC,              Ws,              -,               NC,
-            NC,              -,               NC,              NC,
-            //0x10  + 0/4/8/C
-            NC,              NC,              NC,              NC,
-            NC,              NC,              NC,              NC,
-            NC,              NC,              NC,              NC,
-            NC,              NC,              NC,              NC,
-            //0x20 + 0/4/8/C
-            Ws,              CT|QC|RT|TO|HT,  CT|DOE,          CT|QC|RT|TO|HT,
-            CT|QC|RT|TO|HT,  CT|QC|TO|HT,     CT|QC|RT|TO|HT,  CT|QC|TO|HT,
-            QC,              QC,              CT|QC|TO|HT,     CT|QC|RT|TO|HT,
-            CT|QC,           CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC,
-            //0x30+ 0/4/8/C
-            CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
-            CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
-            CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC,           CT|QC,
-            CT|QC,           CT|QC,           CT|QC,           CT|QC,
-            //0x40+ 0/4/8/C
-            CT|QC,           CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
-            CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
-            CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
-            CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
-            //0x50 + 0/4/8/C
-            CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
-            CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
-            CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC,
-            DOE,/*'\\'*/     CT|QC,           CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
-            //0x60 + 0/4/8/C
-            CT|QC|TO|HT,     CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
-            CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
-            CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
-            CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
-            //0x70 + 0/4/8/C
-            CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
-            CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,
-            CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|RT|TO|HT,  CT|QC|TO,
-            CT|QC|TO|HT,     CT|QC|TO,        CT|QC|TO|HT,     NC,
+        static data: [u32; 256] = [
+            //0x00
+            -,                                                         // 0x00 NUL
+            NC,                                                        // 0x01
+            NC,                                                        // 0x02
+            NC,                                                        // 0x03
+            NC,                                                        // 0x04
+            NC,                                                        // 0x05
+            NC,                                                        // 0x06
+            NC,                                                        // 0x07
+            NC,                                                        // 0x08
+            Ws,                                                        // 0x09 HTAB
+            -,                                                         // 0x0A LF
+            NC,                                                        // 0x0B
+            NC,                                                        // 0x0C
+            -,                                                         // 0x0D CR
+            NC,                                                        // 0x0E
+            NC,                                                        // 0x0F
+            //0x10
+            NC,                                                        // 0x10
+            NC,                                                        // 0x11
+            NC,                                                        // 0x12
+            NC,                                                        // 0x13
+            NC,                                                        // 0x14
+            NC,                                                        // 0x15
+            NC,                                                        // 0x16
+            NC,                                                        // 0x17
+            NC,                                                        // 0x18
+            NC,                                                        // 0x19
+            NC,                                                        // 0x1A
+            NC,                                                        // 0x1B
+            NC,                                                        // 0x1C
+            NC,                                                        // 0x1D
+            NC,                                                        // 0x1E
+            NC,                                                        // 0x1F
+            //0x20
+            Ws|SC|BC,                                                  // 0x20 SP
+            CT|QC|RT|TO|HT|AC|AT|SD|PC|UQ|ST|SC|CO|ATX,                // 0x21 '!'
+            CT|DOE|SPC,                                                // 0x22 '"'
+            CT|QC|RT|TO|HT|AC|AT|GD|ST|SC|CO|ATX,                      // 0x23 '#'
+            CT|QC|RT|TO|HT|AC|AT|SD|PC|UQ|ST|SC|CO|ATX,                // 0x24 '$'
+            CT|QC|TO|HT|PC|UQ|ST|SC|CO|ATX,                            // 0x25 '%'
+            CT|QC|RT|TO|HT|AC|AT|SD|PC|UQ|ST|SC|CO|ATX,                // 0x26 '&'
+            CT|QC|TO|HT|SD|PC|UQ|ST|SC|BC|BN|CO|ATX,                   // 0x27 '\''
+            QC|SD|PC|UQ|SC|BC|BN|CO|SPC,                               // 0x28 '('
+            QC|SD|PC|UQ|SC|BC|BN|CO|SPC,                               // 0x29 ')'
+            CT|QC|TO|HT|SD|PC|UQ|SS|ST|KS|SK|SC|CO|ATX,                // 0x2A '*'
+            CT|QC|RT|TO|HT|AC|AT|SD|PC|UQ|ST|SC|BC|BN|CO|ATX,          // 0x2B '+'
+            CT|QC|SD|PC|UQ|SC|BC|BN|SPC,                               // 0x2C ','
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|ST|SK|SC|BC|BN|CO|ATX,       // 0x2D '-'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|ST|SK|SC|BC|BN|CO|SPC,       // 0x2E '.'
+            CT|QC|GD|UQ|ST|SC|BC|BN|CO|ATX,                            // 0x2F '/'
+            //0x30
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|ST|SK|SC|BC|BN|CO|ATX,       // 0x30 '0'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|ST|SK|SC|BC|BN|CO|ATX,       // 0x31 '1'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|ST|SK|SC|BC|BN|CO|ATX,       // 0x32 '2'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|ST|SK|SC|BC|BN|CO|ATX,       // 0x33 '3'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|ST|SK|SC|BC|BN|CO|ATX,       // 0x34 '4'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|ST|SK|SC|BC|BN|CO|ATX,       // 0x35 '5'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|ST|SK|SC|BC|BN|CO|ATX,       // 0x36 '6'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|ST|SK|SC|BC|BN|CO|ATX,       // 0x37 '7'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|ST|SK|SC|BC|BN|CO|ATX,       // 0x38 '8'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|ST|SK|SC|BC|BN|CO|ATX,       // 0x39 '9'
+            CT|QC|GD|PC|UQ|ST|SC|BC|BN|CO|SPC,                         // 0x3A ':'
+            CT|QC|SD|PC|UQ|SC|SPC,                                     // 0x3B ';'
+            CT|QC|SC|CO|SPC,                                           // 0x3C '<'
+            CT|QC|SD|PC|UQ|SC|BC|BN|CO|ATX,                            // 0x3D '='
+            CT|QC|SC|CO|SPC,                                           // 0x3E '>'
+            CT|QC|GD|UQ|SC|BC|BN|CO|ATX,                               // 0x3F '?'
+            //0x40
+            CT|QC|GD|PC|UQ|SC|CO|SPC,                                  // 0x40 '@'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x41 'A'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x42 'B'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x43 'C'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x44 'D'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x45 'E'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x46 'F'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x47 'G'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x48 'H'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x49 'I'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x4A 'J'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x4B 'K'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x4C 'L'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x4D 'M'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x4E 'N'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x4F 'O'
+            //0x50
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x50 'P'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x51 'Q'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x52 'R'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x53 'S'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x54 'T'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x55 'U'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x56 'V'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x57 'W'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x58 'X'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x59 'Y'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|SC|BC|BN|CO|ATX,       // 0x5A 'Z'
+            CT|QC|GD|SC|CO|SPC,                                        // 0x5B '['
+            DOE|SPC,                                                   // 0x5C '\\'
+            CT|QC|GD|SC|CO|SPC,                                        // 0x5D ']'
+            CT|QC|RT|TO|HT|AC|AT|ST|SC|CO|ATX,                         // 0x5E '^'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|ST|SK|SC|BC|BN|CO|ATX,       // 0x5F '_'
+            //0x60
+            CT|QC|TO|HT|AC|AT|ST|SC|CO|ATX,                            // 0x60 '`'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x61 'a'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x62 'b'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x63 'c'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x64 'd'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x65 'e'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x66 'f'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x67 'g'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x68 'h'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x69 'i'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x6A 'j'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x6B 'k'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x6C 'l'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x6D 'm'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x6E 'n'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x6F 'o'
+            //0x70
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x70 'p'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x71 'q'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x72 'r'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x73 's'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x74 't'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x75 'u'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x76 'v'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x77 'w'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x78 'x'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x79 'y'
+            CT|QC|RT|TO|HT|AC|AT|UR|PC|UQ|SS|ST|KS|SK|SC|BC|BN|CO|ATX, // 0x7A 'z'
+            CT|QC|TO|AT|SC|CO|ATX,                                     // 0x7B '{'
+            CT|QC|TO|HT|AC|AT|ST|SC|CO|ATX,                            // 0x7C '|'
+            CT|QC|TO|AT|SC|CO|ATX,                                     // 0x7D '}'
+            CT|QC|TO|HT|AC|AT|UR|PC|UQ|ST|SC|CO|ATX,                   // 0x7E '~'
+            NC,                                                        // 0x7F DEL
             //0x80
             -, -, -, -, -, -, -, -, -, -, -, -, -, -, -, -,
             //0x90
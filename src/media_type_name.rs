@@ -0,0 +1,340 @@
+use std::error::{Error as StdError};
+use std::fmt::{self, Display};
+
+use lut::{Table};
+use lookup_tables::{
+    MediaTypeChars,
+    RestrictedToken
+};
+
+/// the maximal length of a type or subtype name (RFC6838 section 4.2, MUST)
+pub const MAX_NAME_LENGTH: usize = 127;
+
+/// the recommended maximal length of a type or subtype name (RFC6838 section 4.2, SHOULD)
+pub const RECOMMENDED_MAX_NAME_LENGTH: usize = 64;
+
+/// the registered top-level types
+///
+/// RFC6838 section 4.2 (`text`, `image`, `audio`, `video`, `application`,
+/// `multipart`, `message`), `model` (RFC2077), `example` (RFC4735),
+/// `font` (RFC8081) and `haptics` (RFC9695).
+pub const REGISTERED_TOP_LEVEL_TYPES: &[&str] = &[
+    "application", "audio", "example", "font", "haptics", "image",
+    "message", "model", "multipart", "text", "video"
+];
+
+/// the part of a media type name a error is about
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum NamePart {
+    /// the type name (before the `'/'`)
+    Type,
+    /// the subtype name (after the `'/'`)
+    Subtype
+}
+
+/// the RFC6838 rule which was violated
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Rfc6838Rule {
+    /// the name has to have the form `type-name "/" subtype-name` (section 4.2)
+    TypeSubtypeSeparator,
+    /// the first char of a name has to be `ALPHA` or `DIGIT`, i.e. `restricted-name-first`
+    /// (section 4.2), this includes the name being empty
+    RestrictedNameFirst,
+    /// all other chars have to be `restricted-name-chars` (section 4.2)
+    RestrictedNameChars,
+    /// a name can not be longer than 127 chars (section 4.2)
+    MaxLength,
+    /// a name should not be longer than 64 chars (section 4.2), this is only
+    /// checked if enabled with `MediaTypeNameValidator::with_recommended_length`
+    RecommendedLength,
+    /// the type has to be a registered top-level type (section 4.2.1-4.2.7, RFC8081 ...)
+    RegisteredTopLevelType,
+    /// a facet prefix (`vnd.`, `prs.`, `x.`) has to be followed by a name (section 3)
+    FacetName,
+    /// a structured syntax suffix (after the last `'+'`) has to be a non empty
+    /// name starting with `ALPHA` or `DIGIT` (section 4.2.8)
+    StructuredSyntaxSuffix
+}
+
+impl Rfc6838Rule {
+    /// the section of RFC6838 the rule is defined in
+    pub fn section(&self) -> &'static str {
+        use self::Rfc6838Rule::*;
+        match *self {
+            TypeSubtypeSeparator | RestrictedNameFirst | RestrictedNameChars
+                | MaxLength | RecommendedLength | RegisteredTopLevelType => "4.2",
+            FacetName => "3",
+            StructuredSyntaxSuffix => "4.2.8"
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        use self::Rfc6838Rule::*;
+        match *self {
+            TypeSubtypeSeparator => "media type name has to be of the form type/subtype",
+            RestrictedNameFirst => "name has to start with ALPHA or DIGIT",
+            RestrictedNameChars => "name contains a char which is not a restricted-name-char",
+            MaxLength => "name is longer than 127 chars",
+            RecommendedLength => "name is longer than the recommended 64 chars",
+            RegisteredTopLevelType => "type is not a registered top-level type",
+            FacetName => "facet prefix is not followed by a name",
+            StructuredSyntaxSuffix => "structured syntax suffix is empty or does not start with ALPHA or DIGIT"
+        }
+    }
+}
+
+/// a violation of a RFC6838 rule found in a media type name
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct MediaTypeNameError {
+    /// the part of the name the violation is in, `None` if it's about the whole name
+    pub part: Option<NamePart>,
+    /// the rule which was violated
+    pub rule: Rfc6838Rule,
+    /// the (byte) index in the whole name where the violation was found
+    pub index: usize
+}
+
+impl Display for MediaTypeNameError {
+    fn fmt(&self, fter: &mut fmt::Formatter) -> fmt::Result {
+        let part = match self.part {
+            Some(NamePart::Type) => "type ",
+            Some(NamePart::Subtype) => "subtype ",
+            None => ""
+        };
+        write!(fter, "{}{} at index {} (RFC6838 section {})",
+               part, self.rule.description(), self.index, self.rule.section())
+    }
+}
+
+impl StdError for MediaTypeNameError {}
+
+/// Validates a whole media type name (`type/subtype`) wrt. the registration rules of RFC6838.
+///
+/// In difference to `StrictTokenValidator` (which checks one name at a time) this also checks
+/// that the type is a registered top-level type, that a facet prefix (`vnd.`, `prs.`, `x.`)
+/// is followed by a name and that a structured syntax suffix (e.g. `+json`) is well-formed.
+/// Each length limit is checked separately (for the type and subtype name), the recommended
+/// limit of 64 chars only if enabled.
+///
+/// Parameters are not supported, i.e. a `';'` is a invalid char.
+///
+/// # Example
+///
+/// ```
+/// use media_type_impl_utils::media_type_name::{
+///     MediaTypeNameValidator, NamePart, Rfc6838Rule
+/// };
+///
+/// let validator = MediaTypeNameValidator::new();
+/// assert!(validator.validate("application/vnd.api+json").is_ok());
+///
+/// let err = validator.validate("text/vnd.").unwrap_err();
+/// assert_eq!(err.part, Some(NamePart::Subtype));
+/// assert_eq!(err.rule, Rfc6838Rule::FacetName);
+/// assert_eq!(err.index, 9);
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct MediaTypeNameValidator {
+    recommended_length: bool
+}
+
+impl MediaTypeNameValidator {
+
+    /// create a new validator, not checking the recommended length
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// create a new validator, checking the recommended length if `check` is true
+    pub fn with_recommended_length(check: bool) -> Self {
+        MediaTypeNameValidator { recommended_length: check }
+    }
+
+    /// validates the name returning the first violation
+    ///
+    /// # Error
+    ///
+    /// the first violation found (in order of the index, and for the same index
+    /// in order of the `Rfc6838Rule` variants)
+    pub fn validate(&self, name: &str) -> Result<(), MediaTypeNameError> {
+        match self.violations(name).into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(())
+        }
+    }
+
+    /// returns all violations in the name
+    ///
+    /// For each rule and part at most one violation is returned. The violations are
+    /// ordered by index, and for the same index in order of the `Rfc6838Rule` variants.
+    pub fn violations(&self, name: &str) -> Vec<MediaTypeNameError> {
+        let mut errors = Vec::new();
+        let slash = match name.find('/') {
+            Some(slash) => slash,
+            None => {
+                errors.push(MediaTypeNameError {
+                    part: None, rule: Rfc6838Rule::TypeSubtypeSeparator, index: name.len()
+                });
+                return errors;
+            }
+        };
+        let (type_, subtype) = (&name[..slash], &name[slash+1..]);
+        self.check_restricted_name(NamePart::Type, type_, 0, &mut errors);
+        if !REGISTERED_TOP_LEVEL_TYPES.iter().any(|tlt| tlt.eq_ignore_ascii_case(type_)) {
+            errors.push(MediaTypeNameError {
+                part: Some(NamePart::Type), rule: Rfc6838Rule::RegisteredTopLevelType, index: 0
+            });
+        }
+        let offset = slash + 1;
+        self.check_restricted_name(NamePart::Subtype, subtype, offset, &mut errors);
+        check_facet_and_suffix(subtype, offset, &mut errors);
+        errors.sort_by_key(|err| (err.index, err.rule as usize));
+        errors
+    }
+
+    fn check_restricted_name(
+        &self, part: NamePart, name: &str, offset: usize, errors: &mut Vec<MediaTypeNameError>
+    ) {
+        let mut push = |rule, index| errors.push(MediaTypeNameError { part: Some(part), rule, index });
+        let bytes = name.as_bytes();
+        match bytes.first() {
+            Some(bch) if bch.is_ascii_alphanumeric() => {},
+            _ => push(Rfc6838Rule::RestrictedNameFirst, offset)
+        }
+        let invalid = bytes.iter().skip(1)
+            .position(|&bch| !MediaTypeChars::check_at(bch as usize, RestrictedToken));
+        if let Some(idx) = invalid {
+            push(Rfc6838Rule::RestrictedNameChars, offset + 1 + idx);
+        }
+        if bytes.len() > MAX_NAME_LENGTH {
+            push(Rfc6838Rule::MaxLength, offset + MAX_NAME_LENGTH);
+        } else if self.recommended_length && bytes.len() > RECOMMENDED_MAX_NAME_LENGTH {
+            push(Rfc6838Rule::RecommendedLength, offset + RECOMMENDED_MAX_NAME_LENGTH);
+        }
+    }
+}
+
+fn check_facet_and_suffix(subtype: &str, offset: usize, errors: &mut Vec<MediaTypeNameError>) {
+    let mut push = |rule, index| {
+        errors.push(MediaTypeNameError { part: Some(NamePart::Subtype), rule, index })
+    };
+    let without_suffix = match subtype.rfind('+') {
+        Some(plus) => {
+            if !subtype.as_bytes().get(plus + 1).map(u8::is_ascii_alphanumeric).unwrap_or(false) {
+                push(Rfc6838Rule::StructuredSyntaxSuffix, offset + plus + 1);
+            }
+            &subtype[..plus]
+        },
+        None => subtype
+    };
+    let without_suffix = without_suffix.as_bytes();
+    for prefix in &[&b"vnd."[..], b"prs.", b"x."] {
+        let has_prefix = without_suffix.len() >= prefix.len()
+            && without_suffix[..prefix.len()].eq_ignore_ascii_case(prefix);
+        if has_prefix {
+            if without_suffix.len() == prefix.len() {
+                push(Rfc6838Rule::FacetName, offset + prefix.len());
+            }
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn violations(name: &str) -> Vec<(Option<NamePart>, Rfc6838Rule, usize)> {
+        MediaTypeNameValidator::new().violations(name).into_iter()
+            .map(|err| (err.part, err.rule, err.index))
+            .collect()
+    }
+
+    #[test]
+    fn valid_names() {
+        let validator = MediaTypeNameValidator::with_recommended_length(true);
+        for name in &[
+            "text/plain", "application/vnd.api+json", "image/svg+xml", "application/x.foo",
+            "audio/prs.abc", "Text/Plain", "font/woff2", "model/gltf-binary",
+            "application/vnd.a!#$&-^_.+zip", "multipart/form-data", "example/x", "haptics/ivs"
+        ] {
+            assert_eq!(validator.validate(name), Ok(()), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn missing_slash() {
+        assert_eq!(violations("text"), vec![(None, Rfc6838Rule::TypeSubtypeSeparator, 4)]);
+    }
+
+    #[test]
+    fn restricted_name_rules() {
+        use self::NamePart::*;
+        use self::Rfc6838Rule::*;
+        assert_eq!(violations("text/"), vec![(Some(Subtype), RestrictedNameFirst, 5)]);
+        assert_eq!(violations("text/-a"), vec![(Some(Subtype), RestrictedNameFirst, 5)]);
+        assert_eq!(violations("text/plain;charset=utf-8"),
+                   vec![(Some(Subtype), RestrictedNameChars, 10)]);
+        assert_eq!(violations("text/a~b*c"), vec![(Some(Subtype), RestrictedNameChars, 6)]);
+        assert_eq!(violations("/plain"), vec![
+            (Some(Type), RestrictedNameFirst, 0),
+            (Some(Type), RegisteredTopLevelType, 0)
+        ]);
+    }
+
+    #[test]
+    fn length_limits_are_checked_separately() {
+        use self::NamePart::*;
+        use self::Rfc6838Rule::*;
+        let name65 = "a".repeat(65);
+        let name128 = "a".repeat(128);
+        assert_eq!(violations(&format!("text/{}", name128)), vec![(Some(Subtype), MaxLength, 132)]);
+        assert_eq!(violations(&format!("text/{}", name65)), vec![]);
+        let validator = MediaTypeNameValidator::with_recommended_length(true);
+        let err = validator.validate(&format!("text/{}", name65)).unwrap_err();
+        assert_eq!((err.part, err.rule, err.index), (Some(Subtype), RecommendedLength, 69));
+        let all = validator.violations(&format!("{}/{}", name128, name65)).into_iter()
+            .map(|err| (err.part, err.rule))
+            .collect::<Vec<_>>();
+        assert_eq!(all, vec![
+            (Some(Type), RegisteredTopLevelType),
+            (Some(Type), MaxLength),
+            (Some(Subtype), RecommendedLength)
+        ]);
+    }
+
+    #[test]
+    fn unregistered_top_level_type() {
+        assert_eq!(violations("foo/bar"),
+                   vec![(Some(NamePart::Type), Rfc6838Rule::RegisteredTopLevelType, 0)]);
+    }
+
+    #[test]
+    fn facets() {
+        use self::NamePart::*;
+        use self::Rfc6838Rule::*;
+        assert_eq!(violations("text/vnd."), vec![(Some(Subtype), FacetName, 9)]);
+        assert_eq!(violations("text/VND.+json"), vec![(Some(Subtype), FacetName, 9)]);
+        assert_eq!(violations("text/x."), vec![(Some(Subtype), FacetName, 7)]);
+        // "x-" is not a facet
+        assert_eq!(violations("text/x-"), vec![]);
+        // non us-ascii chars do not cause a panic
+        assert_eq!(violations("text/v\u{e4}"), vec![(Some(Subtype), RestrictedNameChars, 6)]);
+    }
+
+    #[test]
+    fn structured_syntax_suffix() {
+        use self::NamePart::*;
+        use self::Rfc6838Rule::*;
+        assert_eq!(violations("application/ld+"), vec![(Some(Subtype), StructuredSyntaxSuffix, 15)]);
+        assert_eq!(violations("application/ld+.json"),
+                   vec![(Some(Subtype), StructuredSyntaxSuffix, 15)]);
+    }
+
+    #[test]
+    fn display() {
+        let err = MediaTypeNameValidator::new().validate("text/vnd.").unwrap_err();
+        assert_eq!(err.to_string(),
+                   "subtype facet prefix is not followed by a name at index 9 (RFC6838 section 3)");
+    }
+}
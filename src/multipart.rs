@@ -0,0 +1,920 @@
+use std::borrow::Cow;
+use std::collections::hash_map::RandomState;
+use std::error::{Error as StdError};
+use std::fmt::{self, Display};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Read, Write};
+use std::str;
+
+use content_disposition::{
+    raw_params, content,
+    ContentDisposition, DispositionType, DispositionSyntax,
+    DispositionParseError, DispositionBuildError
+};
+use folding::{unfold_with_limits, UnfoldMode, HeaderFolder, FoldingError, MAX_LINE_LENGTH};
+use limits::{Limits, LimitExceeded, LimitedError};
+use quoted_string::{validate_boundary, boundary_param_value, BoundaryError};
+use scan::find_first_not_http_token;
+
+/// the default limit for the number of parts of a multipart body
+pub const DEFAULT_MAX_PARTS: usize = 1000;
+
+/// the default limit for the size (in bytes) of the header section of a single part
+pub const DEFAULT_MAX_HEADER_SIZE: usize = 16 * 1024;
+
+/// the default limit for the size (in bytes) of a body read from a `io::Read`
+pub const DEFAULT_MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// error returned if a multipart body could not be parsed
+#[derive(Debug)]
+pub enum MultipartError {
+    /// the media type is not a `multipart/*` media type
+    NotMultipart,
+    /// the media type has no `boundary` parameter
+    MissingBoundary,
+    /// the boundary is not valid
+    InvalidBoundary(BoundaryError),
+    /// the body does not contain the first boundary delimiter
+    MissingFirstDelimiter,
+    /// the body ends before the close delimiter
+    MissingCloseDelimiter,
+    /// a boundary delimiter is not followed by `"\r\n"` or `"--"` (at the given index)
+    InvalidDelimiter {
+        /// the (byte) index in the body
+        index: usize
+    },
+    /// the header section of a part is not valid (at the given index)
+    InvalidHeader {
+        /// the (byte) index in the body
+        index: usize
+    },
+    /// the body has more parts than allowed
+    TooManyParts,
+    /// the header section of a part is larger than allowed
+    HeaderTooLarge,
+    /// the body read from a `io::Read` is larger than allowed
+    BodyTooLarge,
+    /// a header field value of a part exceeds the `Limits` (the index is wrt. the body)
+    LimitExceeded(LimitExceeded),
+    /// reading the body failed
+    Io(io::Error)
+}
+
+impl Display for MultipartError {
+    fn fmt(&self, fter: &mut fmt::Formatter) -> fmt::Result {
+        use self::MultipartError::*;
+        match *self {
+            NotMultipart => fter.write_str("media type is not a multipart media type"),
+            MissingBoundary => fter.write_str("multipart media type has no boundary parameter"),
+            InvalidBoundary(ref err) => Display::fmt(err, fter),
+            MissingFirstDelimiter => fter.write_str("multipart body does not contain the boundary"),
+            MissingCloseDelimiter => fter.write_str("multipart body ends before the close delimiter"),
+            InvalidDelimiter { index } => write!(fter, "invalid multipart delimiter line at {}", index),
+            InvalidHeader { index } => write!(fter, "invalid header in multipart body at {}", index),
+            TooManyParts => fter.write_str("multipart body has too many parts"),
+            HeaderTooLarge => fter.write_str("header section of a part is too large"),
+            BodyTooLarge => fter.write_str("multipart body is too large"),
+            LimitExceeded(ref err) => write!(fter, "header in multipart body: {}", err),
+            Io(ref err) => write!(fter, "reading multipart body failed: {}", err)
+        }
+    }
+}
+
+impl StdError for MultipartError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match *self {
+            MultipartError::InvalidBoundary(ref err) => Some(err),
+            MultipartError::LimitExceeded(ref err) => Some(err),
+            MultipartError::Io(ref err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl From<BoundaryError> for MultipartError {
+    fn from(err: BoundaryError) -> Self {
+        MultipartError::InvalidBoundary(err)
+    }
+}
+
+impl From<io::Error> for MultipartError {
+    fn from(err: io::Error) -> Self {
+        MultipartError::Io(err)
+    }
+}
+
+impl From<LimitExceeded> for MultipartError {
+    fn from(err: LimitExceeded) -> Self {
+        MultipartError::LimitExceeded(err)
+    }
+}
+
+/// returns the (validated) `boundary` parameter of a `multipart/*` Content-Type header value
+///
+/// # Example
+///
+/// ```
+/// use media_type_impl_utils::multipart::boundary_from_content_type;
+///
+/// let boundary = boundary_from_content_type("multipart/form-data; boundary=\"=_a?b\"").unwrap();
+/// assert_eq!(boundary, "=_a?b");
+/// assert!(boundary_from_content_type("text/plain; boundary=a").is_err());
+/// ```
+///
+/// # Error
+///
+/// if the media type is not `multipart/*`, has no boundary or it is not valid, or if the
+/// header exceeds the default `Limits`
+pub fn boundary_from_content_type(content_type: &str) -> Result<String, MultipartError> {
+    boundary_from_content_type_with_limits(content_type, &Limits::new())
+}
+
+/// like `boundary_from_content_type` but with the given limits
+///
+/// # Error
+///
+/// see `boundary_from_content_type`, a `MultipartError::LimitExceeded` is returned if the
+/// header exceeds a limit (even if the boundary parameter is within them)
+pub fn boundary_from_content_type_with_limits(content_type: &str, limits: &Limits)
+    -> Result<String, MultipartError>
+{
+    let media_type = content_type.split(';').next().unwrap_or("").trim();
+    let is_multipart = media_type.len() > 10
+        && media_type.is_char_boundary(10)
+        && media_type[..10].eq_ignore_ascii_case("multipart/");
+    if !is_multipart {
+        return Err(MultipartError::NotMultipart);
+    }
+    let boundary = raw_params(content_type, limits)?
+        .into_iter()
+        .find(|&(name, _)| name.eq_ignore_ascii_case("boundary"))
+        .map(|(_, value)| content(value))
+        .ok_or(MultipartError::MissingBoundary)?;
+    validate_boundary(&boundary)?;
+    Ok(boundary.into_owned())
+}
+
+/// a (unfolded) header field of a part
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct PartHeader {
+    /// the field name
+    pub name: String,
+    /// the unfolded field value, without leading and trailing ws
+    pub value: String
+}
+
+/// a part of a multipart body
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Part<'a> {
+    /// the header fields of the part
+    pub headers: Vec<PartHeader>,
+    /// the body of the part
+    pub body: Cow<'a, [u8]>
+}
+
+impl<'a> Part<'a> {
+
+    /// returns the value of the first header field with the given name (case insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|header| header.name.eq_ignore_ascii_case(name))
+            .map(|header| &*header.value)
+    }
+
+    /// the value of the `Content-Type` header field, if there is one
+    pub fn content_type(&self) -> Option<&str> {
+        self.header("Content-Type")
+    }
+
+    /// the parsed `Content-Disposition` header field (wrt. the mime grammar), if there is one
+    pub fn content_disposition(&self) -> Option<Result<ContentDisposition, DispositionParseError>> {
+        self.header("Content-Disposition")
+            .map(|value| ContentDisposition::parse(value, DispositionSyntax::Mime))
+    }
+
+    /// returns the part with a owned body
+    pub fn into_owned(self) -> Part<'static> {
+        Part { headers: self.headers, body: Cow::Owned(self.body.into_owned()) }
+    }
+}
+
+/// a parser for `multipart/*` bodies (RFC2046, RFC7578)
+///
+/// The preamble and epilogue are ignored. Header fields of parts are unfolded following the
+/// same rules as `FWSState` and have to be valid utf-8 (RFC6532), use
+/// `Part::content_disposition` to parse the Content-Disposition header field.
+///
+/// To protect against hostile input the number of parts and the size of the header section
+/// of each part are limited, when reading from a `io::Read` the body size is limited, too.
+/// Additionally the length and number of folds of each header field value are limited
+/// (see `with_limits`, by default `Limits::new()` is used).
+///
+/// # Example
+///
+/// ```
+/// use media_type_impl_utils::multipart::{MultipartParser, boundary_from_content_type};
+///
+/// let boundary = boundary_from_content_type("multipart/form-data; boundary=xyz").unwrap();
+/// let body = b"--xyz\r\nContent-Disposition: form-data; name=a\r\n\r\nhy\r\n--xyz--\r\n";
+/// let parts = MultipartParser::new().parse(body, &boundary).unwrap();
+/// assert_eq!(parts.len(), 1);
+/// assert_eq!(&*parts[0].body, b"hy");
+/// let cd = parts[0].content_disposition().unwrap().unwrap();
+/// assert_eq!(cd.name.unwrap(), "a");
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct MultipartParser {
+    max_parts: usize,
+    max_header_size: usize,
+    max_body_size: usize,
+    limits: Limits
+}
+
+impl Default for MultipartParser {
+    fn default() -> Self {
+        MultipartParser {
+            max_parts: DEFAULT_MAX_PARTS,
+            max_header_size: DEFAULT_MAX_HEADER_SIZE,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            limits: Limits::new()
+        }
+    }
+}
+
+impl MultipartParser {
+
+    /// create a new parser with the default limits
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// returns a parser allowing at most `max_parts` parts
+    pub fn with_max_parts(self, max_parts: usize) -> Self {
+        MultipartParser { max_parts, ..self }
+    }
+
+    /// returns a parser allowing header sections of at most `max_header_size` bytes per part
+    pub fn with_max_header_size(self, max_header_size: usize) -> Self {
+        MultipartParser { max_header_size, ..self }
+    }
+
+    /// returns a parser reading at most `max_body_size` bytes from a `io::Read`
+    pub fn with_max_body_size(self, max_body_size: usize) -> Self {
+        MultipartParser { max_body_size, ..self }
+    }
+
+    /// returns a parser checking the header field values of the parts wrt. the limits
+    ///
+    /// Only the length and number of folds limits apply, as the header values are not
+    /// parsed further (e.g. use `ContentDisposition::parse_with_limits` for that).
+    pub fn with_limits(self, limits: Limits) -> Self {
+        MultipartParser { limits, ..self }
+    }
+
+    /// parses the multipart body, the bodies of the parts borrow from the input
+    ///
+    /// # Error
+    ///
+    /// if the boundary or body is not valid or a limit is exceeded
+    pub fn parse<'a>(&self, body: &'a [u8], boundary: &str) -> Result<Vec<Part<'a>>, MultipartError> {
+        validate_boundary(boundary)?;
+        let dash_boundary = format!("--{}", boundary);
+        let dash_boundary = dash_boundary.as_bytes();
+        let delimiter = format!("\r\n--{}", boundary);
+        let delimiter = delimiter.as_bytes();
+
+        let mut pos =
+            if body.starts_with(dash_boundary) {
+                dash_boundary.len()
+            } else {
+                find(body, delimiter, 0).ok_or(MultipartError::MissingFirstDelimiter)? + delimiter.len()
+            };
+
+        let mut parts = Vec::new();
+        loop {
+            match delimiter_end(body, pos)? {
+                DelimiterEnd::Close => return Ok(parts),
+                DelimiterEnd::Part(start) => {
+                    if parts.len() >= self.max_parts {
+                        return Err(MultipartError::TooManyParts);
+                    }
+                    let end = find(body, delimiter, start).ok_or(MultipartError::MissingCloseDelimiter)?;
+                    parts.push(self.parse_part(body, start, end)?);
+                    pos = end + delimiter.len();
+                }
+            }
+        }
+    }
+
+    /// reads and parses the multipart body, returning parts owning their body
+    ///
+    /// # Error
+    ///
+    /// like `parse` and if reading fails or the body is larger than the max body size
+    pub fn parse_reader<R: Read>(&self, reader: R, boundary: &str) -> Result<Vec<Part<'static>>, MultipartError> {
+        let mut body = Vec::new();
+        reader.take(self.max_body_size as u64 + 1).read_to_end(&mut body)?;
+        if body.len() > self.max_body_size {
+            return Err(MultipartError::BodyTooLarge);
+        }
+        let parts = self.parse(&body, boundary)?;
+        Ok(parts.into_iter().map(Part::into_owned).collect())
+    }
+
+    fn parse_part<'a>(&self, body: &'a [u8], start: usize, end: usize) -> Result<Part<'a>, MultipartError> {
+        let part = &body[start..end];
+        let (header_end, body_start) =
+            if part.starts_with(b"\r\n") {
+                (0, 2)
+            } else {
+                match find(part, b"\r\n\r\n", 0) {
+                    Some(idx) => (idx, idx + 4),
+                    None if part.len() > self.max_header_size => return Err(MultipartError::HeaderTooLarge),
+                    None => return Err(MultipartError::InvalidHeader { index: end })
+                }
+            };
+        if header_end > self.max_header_size {
+            return Err(MultipartError::HeaderTooLarge);
+        }
+        let headers = parse_headers(&part[..header_end], start, &self.limits)?;
+        Ok(Part { headers, body: Cow::Borrowed(&part[body_start..]) })
+    }
+}
+
+enum DelimiterEnd {
+    Close,
+    /// the part starts at the given index
+    Part(usize)
+}
+
+/// handles the rest of a delimiter line (`pos` is the index after the boundary)
+fn delimiter_end(body: &[u8], pos: usize) -> Result<DelimiterEnd, MultipartError> {
+    let rest = &body[pos..];
+    if rest.starts_with(b"--") {
+        return Ok(DelimiterEnd::Close);
+    }
+    // transport-padding
+    let padding = rest.iter().take_while(|&&bch| bch == b' ' || bch == b'\t').count();
+    let rest = &rest[padding..];
+    if rest.starts_with(b"\r\n") {
+        Ok(DelimiterEnd::Part(pos + padding + 2))
+    } else if rest.is_empty() || rest == b"\r" {
+        Err(MultipartError::MissingCloseDelimiter)
+    } else {
+        Err(MultipartError::InvalidDelimiter { index: pos + padding })
+    }
+}
+
+/// parses the header section (without the final empty line), `offset` is it's index in the body
+fn parse_headers(section: &[u8], offset: usize, limits: &Limits) -> Result<Vec<PartHeader>, MultipartError> {
+    let mut headers = Vec::new();
+    let mut start = 0;
+    while start < section.len() {
+        // a field ends at a "\r\n" which is not followed by ws (i.e. is not a FWS)
+        let mut search_from = start;
+        let mut end = section.len();
+        while let Some(idx) = find(section, b"\r\n", search_from) {
+            match section.get(idx + 2) {
+                Some(&b' ') | Some(&b'\t') => search_from = idx + 2,
+                _ => {
+                    end = idx;
+                    break;
+                }
+            }
+        }
+        headers.push(parse_header(&section[start..end], offset + start, limits)?);
+        start = end + 2;
+    }
+    Ok(headers)
+}
+
+fn parse_header(field: &[u8], offset: usize, limits: &Limits) -> Result<PartHeader, MultipartError> {
+    let invalid = |idx: usize| MultipartError::InvalidHeader { index: offset + idx };
+    let colon = field.iter().position(|&bch| bch == b':');
+    let name = &field[..colon.unwrap_or(field.len())];
+    if let Some(idx) = name.iter().position(|bch| !(0x21..=0x7e).contains(bch)) {
+        return Err(invalid(idx));
+    }
+    let colon = colon.ok_or_else(|| invalid(field.len()))?;
+    if name.is_empty() {
+        return Err(invalid(0));
+    }
+    let value = unfold_with_limits(&field[colon + 1..], UnfoldMode::Strict, limits)
+        .map_err(|err| match err {
+            LimitedError::Syntax(idx, _) => invalid(colon + 1 + idx),
+            LimitedError::Limit(err) => MultipartError::LimitExceeded(LimitExceeded {
+                index: offset + colon + 1 + err.index,
+                ..err
+            })
+        })?;
+    let value = str::from_utf8(&value).map_err(|err| invalid(colon + 1 + err.valid_up_to()))?;
+    Ok(PartHeader {
+        // SAFE: the name is us-ascii
+        name: String::from_utf8(name.to_owned()).expect("[BUG] name is us-ascii"),
+        value: value.trim_matches(&[' ', '\t'][..]).to_owned()
+    })
+}
+
+/// returns the index of the first occurrence of `needle` in `haystack` at or after `from`
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    if needle.is_empty() || haystack.len() < needle.len() {
+        return None;
+    }
+    let first = needle[0];
+    let last_start = haystack.len() - needle.len();
+    let mut idx = from;
+    while idx <= last_start {
+        let offset = haystack[idx..=last_start].iter().position(|&bch| bch == first)?;
+        idx += offset;
+        if &haystack[idx..idx + needle.len()] == needle {
+            return Some(idx);
+        }
+        idx += 1;
+    }
+    None
+}
+
+/// the number of boundaries `MultipartWriter` tries before giving up
+const MAX_BOUNDARY_ATTEMPTS: usize = 8;
+
+/// error returned if a multipart body could not be written
+#[derive(Debug)]
+pub enum MultipartWriteError {
+    /// the multipart subtype is not a token
+    InvalidSubtype,
+    /// the (given) boundary is not valid
+    InvalidBoundary(BoundaryError),
+    /// the boundary appears in a part (for generated boundaries: in all tried ones)
+    BoundaryCollision,
+    /// a header field of a part can not be written
+    InvalidHeader(FoldingError),
+    /// the Content-Disposition of a part can not be written
+    InvalidDisposition(DispositionBuildError),
+    /// writing the body failed
+    Io(io::Error)
+}
+
+impl Display for MultipartWriteError {
+    fn fmt(&self, fter: &mut fmt::Formatter) -> fmt::Result {
+        use self::MultipartWriteError::*;
+        match *self {
+            InvalidSubtype => fter.write_str("multipart subtype is not a token"),
+            InvalidBoundary(ref err) => Display::fmt(err, fter),
+            BoundaryCollision => fter.write_str("multipart boundary appears in a part"),
+            InvalidHeader(ref err) => write!(fter, "invalid header field in part: {}", err),
+            InvalidDisposition(ref err) => write!(fter, "invalid Content-Disposition in part: {}", err),
+            Io(ref err) => write!(fter, "writing multipart body failed: {}", err)
+        }
+    }
+}
+
+impl StdError for MultipartWriteError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        use self::MultipartWriteError::*;
+        match *self {
+            InvalidBoundary(ref err) => Some(err),
+            InvalidHeader(ref err) => Some(err),
+            InvalidDisposition(ref err) => Some(err),
+            Io(ref err) => Some(err),
+            InvalidSubtype | BoundaryCollision => None
+        }
+    }
+}
+
+impl From<BoundaryError> for MultipartWriteError {
+    fn from(err: BoundaryError) -> Self {
+        MultipartWriteError::InvalidBoundary(err)
+    }
+}
+
+impl From<io::Error> for MultipartWriteError {
+    fn from(err: io::Error) -> Self {
+        MultipartWriteError::Io(err)
+    }
+}
+
+/// a part to be written by a `MultipartWriter`
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct OutgoingPart {
+    /// the Content-Disposition of the part
+    pub content_disposition: Option<ContentDisposition>,
+    /// the Content-Type of the part
+    pub content_type: Option<String>,
+    /// other header fields as (name, value) pairs
+    pub headers: Vec<(String, String)>,
+    /// the body of the part
+    pub body: Vec<u8>
+}
+
+impl OutgoingPart {
+
+    /// create a new part without any header fields
+    pub fn new<B: Into<Vec<u8>>>(body: B) -> Self {
+        OutgoingPart { content_disposition: None, content_type: None, headers: Vec::new(), body: body.into() }
+    }
+
+    /// create a `form-data` part for a (text) field
+    pub fn form_field<N: Into<String>, V: Into<Vec<u8>>>(name: N, value: V) -> Self {
+        OutgoingPart::new(value)
+            .with_content_disposition(ContentDisposition::new(DispositionType::FormData).with_name(name))
+    }
+
+    /// create a `form-data` part for a file
+    ///
+    /// The filename is written as quoted-string containing utf-8 (RFC7578 does not allow
+    /// `filename*`) with `'\r'`, `'\n'` and `'"'` percent encoded like HTML does.
+    pub fn form_file<N, F, B>(name: N, filename: F, content_type: &str, body: B) -> Self
+        where N: Into<String>, F: Into<String>, B: Into<Vec<u8>>
+    {
+        let cd = ContentDisposition::new(DispositionType::FormData)
+            .with_name(name)
+            .with_filename(filename);
+        OutgoingPart::new(body)
+            .with_content_disposition(cd)
+            .with_content_type(content_type)
+    }
+
+    /// returns the part with the given Content-Disposition
+    pub fn with_content_disposition(self, content_disposition: ContentDisposition) -> Self {
+        OutgoingPart { content_disposition: Some(content_disposition), ..self }
+    }
+
+    /// returns the part with the given Content-Type
+    pub fn with_content_type<S: Into<String>>(self, content_type: S) -> Self {
+        OutgoingPart { content_type: Some(content_type.into()), ..self }
+    }
+
+    /// returns the part with a additional header field
+    pub fn with_header<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    fn header_fields(&self) -> Result<Vec<String>, MultipartWriteError> {
+        // only fold if the hard line length limit requires it, as some form-data
+        // parsers do not handle folding
+        let folder = HeaderFolder::with_line_length(MAX_LINE_LENGTH);
+        let structured = folder.with_structured(true);
+        let fold = |folder: &HeaderFolder, name: &str, value: &str| {
+            folder.fold(name, value).map_err(MultipartWriteError::InvalidHeader)
+        };
+        let mut fields = Vec::new();
+        if let Some(ref cd) = self.content_disposition {
+            let value = cd.to_header_value().map_err(MultipartWriteError::InvalidDisposition)?;
+            fields.push(fold(&structured, "Content-Disposition", &value)?);
+        }
+        if let Some(ref content_type) = self.content_type {
+            fields.push(fold(&structured, "Content-Type", content_type)?);
+        }
+        // the fields added with `with_header` might be unstructured
+        for (name, value) in self.headers.iter() {
+            fields.push(fold(&folder, name, value)?);
+        }
+        Ok(fields)
+    }
+}
+
+/// writes `multipart/*` bodies (RFC2046, RFC7578)
+///
+/// If no boundary is given a random one is generated (`"=_"` followed by 28 alphanumeric
+/// chars, as `"=_"` can not appear in quoted-printable encoded content), it is checked that
+/// the boundary does not appear in any part (generating a new one if needed). Header fields
+/// are only folded if needed to keep to `MAX_LINE_LENGTH`.
+///
+/// Note that for `form-data` parts non us-ascii filenames are **not** written as `filename*`
+/// but as quoted-string containing utf-8, as RFC7578 (section 4.2) forbids `filename*` and
+/// browsers and most receivers only support this form. For other parts (e.g. in
+/// `multipart/mixed`) `filename*` is used, with a us-ascii fallback in `filename`.
+///
+/// # Example
+///
+/// ```
+/// use media_type_impl_utils::multipart::{MultipartWriter, MultipartParser, OutgoingPart};
+///
+/// let mut writer = MultipartWriter::new("form-data");
+/// writer.add_part(OutgoingPart::form_field("a", "hy"));
+/// writer.add_part(OutgoingPart::form_file("f", "r\u{e9}sum\u{e9}.txt", "text/plain", "content"));
+/// let (content_type, body) = writer.to_bytes().unwrap();
+/// assert!(content_type.starts_with("multipart/form-data; boundary=\"=_"));
+///
+/// let boundary = writer.boundary().unwrap();
+/// let parts = MultipartParser::new().parse(&body, boundary).unwrap();
+/// assert_eq!(parts[1].header("Content-Disposition").unwrap(),
+///            "form-data; name=\"f\"; filename=\"r\u{e9}sum\u{e9}.txt\"");
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct MultipartWriter {
+    subtype: String,
+    boundary: Option<String>,
+    parts: Vec<OutgoingPart>
+}
+
+impl MultipartWriter {
+
+    /// create a new writer for the given multipart subtype (e.g. `"form-data"` or `"mixed"`)
+    ///
+    /// The subtype is checked to be a token when writing (see `write_to`).
+    pub fn new<S: Into<String>>(subtype: S) -> Self {
+        MultipartWriter { subtype: subtype.into(), boundary: None, parts: Vec::new() }
+    }
+
+    /// use the given boundary instead of generating one
+    ///
+    /// # Error
+    ///
+    /// if the boundary is not valid
+    pub fn set_boundary<S: Into<String>>(&mut self, boundary: S) -> Result<(), MultipartWriteError> {
+        let boundary = boundary.into();
+        validate_boundary(&boundary).map_err(MultipartWriteError::InvalidBoundary)?;
+        self.boundary = Some(boundary);
+        Ok(())
+    }
+
+    /// the boundary used, if one was set or already generated
+    pub fn boundary(&self) -> Option<&str> {
+        self.boundary.as_deref()
+    }
+
+    /// adds a part
+    pub fn add_part(&mut self, part: OutgoingPart) {
+        self.parts.push(part);
+    }
+
+    /// the parts which will be written
+    pub fn parts(&self) -> &[OutgoingPart] {
+        &self.parts
+    }
+
+    /// writes the body into `out` and returns the Content-Type header value (with the boundary)
+    ///
+    /// # Error
+    ///
+    /// if the subtype is not a token, the boundary appears in a part, a header field can
+    /// not be written or writing fails
+    pub fn write_to<W: Write>(&mut self, out: &mut W) -> Result<String, MultipartWriteError> {
+        let subtype = self.subtype.as_bytes();
+        if subtype.is_empty() || find_first_not_http_token(subtype).is_some() {
+            return Err(MultipartWriteError::InvalidSubtype);
+        }
+        let mut parts = Vec::with_capacity(self.parts.len());
+        for part in self.parts.iter() {
+            parts.push((part.header_fields()?, &part.body));
+        }
+        let collides = |boundary: &str| parts.iter().any(|&(ref fields, body)| {
+            find(body, boundary.as_bytes(), 0).is_some()
+                || fields.iter().any(|field| field.contains(boundary))
+        });
+        let boundary = match self.boundary {
+            Some(ref boundary) if collides(boundary) => return Err(MultipartWriteError::BoundaryCollision),
+            Some(ref boundary) => boundary.clone(),
+            None => (0..MAX_BOUNDARY_ATTEMPTS)
+                .map(|_| random_boundary())
+                .find(|boundary| !collides(boundary))
+                .ok_or(MultipartWriteError::BoundaryCollision)?
+        };
+
+        for (fields, body) in parts {
+            write!(out, "--{}\r\n", boundary)?;
+            for field in fields {
+                write!(out, "{}\r\n", field)?;
+            }
+            out.write_all(b"\r\n")?;
+            out.write_all(body)?;
+            out.write_all(b"\r\n")?;
+        }
+        write!(out, "--{}--\r\n", boundary)?;
+
+        let content_type = format!("multipart/{}; boundary={}",
+                                   self.subtype, boundary_param_value(&boundary)?);
+        self.boundary = Some(boundary);
+        Ok(content_type)
+    }
+
+    /// writes the body, returns the Content-Type header value and the body
+    ///
+    /// # Error
+    ///
+    /// see `write_to`
+    pub fn to_bytes(&mut self) -> Result<(String, Vec<u8>), MultipartWriteError> {
+        let mut out = Vec::new();
+        let content_type = self.write_to(&mut out)?;
+        Ok((content_type, out))
+    }
+}
+
+/// generates a random boundary (`"=_"` followed by 28 alphanumeric chars)
+fn random_boundary() -> String {
+    const CHARS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+    let mut boundary = String::from("=_");
+    // each `RandomState` is randomly seeded
+    let mut random = 0u64;
+    for idx in 0..28 {
+        if idx % 8 == 0 {
+            random = RandomState::new().build_hasher().finish();
+        }
+        boundary.push(CHARS[(random % CHARS.len() as u64) as usize] as char);
+        random /= CHARS.len() as u64;
+    }
+    boundary
+}
+
+#[cfg(test)]
+mod test {
+    use limits::LimitKind;
+    use super::*;
+
+    const BODY: &[u8] = b"preamble\r\n\
+        --xyz\r\n\
+        Content-Disposition: form-data; name=\"a\"\r\n\
+        \r\n\
+        value a\r\n\
+        --xyz  \r\n\
+        Content-Disposition: form-data; name=\"f\";\r\n filename=\"r\xC3\xA9sum\xC3\xA9.txt\"\r\n\
+        Content-Type: text/plain\r\n\
+        \r\n\
+        line1\r\n--xy not the boundary\r\n\r\n\
+        --xyz--\r\n\
+        epilogue";
+
+    #[test]
+    fn boundary_from_content_type_works() {
+        assert_eq!(boundary_from_content_type("Multipart/Mixed; charset=x; Boundary=abc").unwrap(), "abc");
+        assert!(matches!(boundary_from_content_type("multipart/mixed"), Err(MultipartError::MissingBoundary)));
+        assert!(matches!(boundary_from_content_type("multipart/mixed; boundary=\"a \""),
+                         Err(MultipartError::InvalidBoundary(BoundaryError::EndsWithSpace))));
+        assert!(matches!(boundary_from_content_type("multipart"), Err(MultipartError::NotMultipart)));
+    }
+
+    #[test]
+    fn boundary_from_content_type_checks_limits() {
+        let hostile = format!("multipart/mixed; boundary=abc; a={}", "(".repeat(1_000));
+        match boundary_from_content_type(&hostile) {
+            Err(MultipartError::LimitExceeded(err)) => {
+                assert_eq!((err.kind, err.index), (LimitKind::CommentDepth, 41));
+            },
+            other => panic!("unexpected result: {:?}", other)
+        }
+        let limits = Limits::new().with_max_params(1);
+        let content_type = "multipart/mixed; boundary=abc; charset=x";
+        assert!(matches!(boundary_from_content_type_with_limits(content_type, &limits),
+                         Err(MultipartError::LimitExceeded(_))));
+        assert_eq!(boundary_from_content_type_with_limits(content_type, &Limits::unlimited()).unwrap(), "abc");
+    }
+
+    #[test]
+    fn parse_body() {
+        let parts = MultipartParser::new().parse(BODY, "xyz").unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].headers, vec![PartHeader {
+            name: "Content-Disposition".to_owned(),
+            value: "form-data; name=\"a\"".to_owned()
+        }]);
+        assert_eq!(&*parts[0].body, b"value a");
+        assert_eq!(parts[1].content_type(), Some("text/plain"));
+        let cd = parts[1].content_disposition().unwrap().unwrap();
+        assert_eq!(cd.filename.unwrap(), "r\u{e9}sum\u{e9}.txt");
+        assert_eq!(&*parts[1].body, &b"line1\r\n--xy not the boundary\r\n"[..]);
+    }
+
+    #[test]
+    fn parse_reader_owns_parts() {
+        let parts = MultipartParser::new().parse_reader(BODY, "xyz").unwrap();
+        assert_eq!(parts, MultipartParser::new().parse(BODY, "xyz").unwrap());
+        assert!(matches!(MultipartParser::new().with_max_body_size(10).parse_reader(BODY, "xyz"),
+                         Err(MultipartError::BodyTooLarge)));
+    }
+
+    #[test]
+    fn part_without_headers() {
+        let parts = MultipartParser::new().parse(b"--b\r\n\r\nx\r\n--b\r\n\r\n\r\n--b--", "b").unwrap();
+        assert_eq!(parts.len(), 2);
+        assert!(parts[0].headers.is_empty());
+        assert_eq!(&*parts[0].body, b"x");
+        assert_eq!(&*parts[1].body, b"");
+    }
+
+    #[test]
+    fn limits() {
+        assert!(matches!(MultipartParser::new().with_max_parts(1).parse(BODY, "xyz"),
+                         Err(MultipartError::TooManyParts)));
+        assert!(matches!(MultipartParser::new().with_max_header_size(20).parse(BODY, "xyz"),
+                         Err(MultipartError::HeaderTooLarge)));
+        let parser = MultipartParser::new().with_limits(Limits::new().with_max_length(20));
+        match parser.parse(BODY, "xyz") {
+            Err(MultipartError::LimitExceeded(err)) => {
+                assert_eq!(err.kind, LimitKind::Length);
+                assert_eq!(err.index, 119);
+            },
+            other => panic!("unexpected result: {:?}", other)
+        }
+    }
+
+    #[test]
+    fn invalid_bodies() {
+        let parser = MultipartParser::new();
+        assert!(matches!(parser.parse(b"--a\r\n\r\nx", "b"), Err(MultipartError::MissingFirstDelimiter)));
+        assert!(matches!(parser.parse(b"--b\r\n\r\nx", "b"), Err(MultipartError::MissingCloseDelimiter)));
+        assert!(matches!(parser.parse(b"--b\r\n\r\nx\r\n--b", "b"), Err(MultipartError::MissingCloseDelimiter)));
+        assert!(matches!(parser.parse(b"--bx\r\n\r\n\r\n--b--", "b"), Err(MultipartError::InvalidDelimiter { index: 3 })));
+        assert!(matches!(parser.parse(b"--b\r\nNo Colon\r\n\r\n\r\n--b--", "b"), Err(MultipartError::InvalidHeader { index: 7 })));
+        assert!(matches!(parser.parse(b"--b\r\nA: x\ry\r\n\r\n\r\n--b--", "b"), Err(MultipartError::InvalidHeader { index: 10 })));
+        assert!(matches!(parser.parse(b"--b\r\nA: \xFF\r\n\r\n\r\n--b--", "b"), Err(MultipartError::InvalidHeader { index: 8 })));
+        assert!(matches!(parser.parse(b"", "b "), Err(MultipartError::InvalidBoundary(_))));
+    }
+
+    #[test]
+    fn random_boundaries_are_valid_and_quoted() {
+        let boundary = random_boundary();
+        assert_eq!(boundary.len(), 30);
+        assert!(validate_boundary(&boundary).is_ok());
+        assert_ne!(boundary, random_boundary());
+        let mut writer = MultipartWriter::new("mixed");
+        writer.add_part(OutgoingPart::new("x"));
+        let (content_type, _) = writer.to_bytes().unwrap();
+        assert_eq!(content_type, format!("multipart/mixed; boundary=\"{}\"", writer.boundary().unwrap()));
+    }
+
+    #[test]
+    fn write_and_parse_round_trip() {
+        let mut writer = MultipartWriter::new("form-data");
+        writer.set_boundary("xyz").unwrap();
+        writer.add_part(OutgoingPart::form_field("a b", "value"));
+        writer.add_part(OutgoingPart::form_file("f", "a.txt", "text/plain", &b"line\r\n"[..])
+            .with_header("X-Custom", "1"));
+        let (content_type, body) = writer.to_bytes().unwrap();
+        assert_eq!(content_type, "multipart/form-data; boundary=xyz");
+        assert_eq!(body, &b"--xyz\r\n\
+            Content-Disposition: form-data; name=\"a b\"\r\n\
+            \r\n\
+            value\r\n\
+            --xyz\r\n\
+            Content-Disposition: form-data; name=\"f\"; filename=\"a.txt\"\r\n\
+            Content-Type: text/plain\r\n\
+            X-Custom: 1\r\n\
+            \r\n\
+            line\r\n\r\n\
+            --xyz--\r\n"[..]);
+
+        let boundary = boundary_from_content_type(&content_type).unwrap();
+        let parts = MultipartParser::new().parse(&body, &boundary).unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].content_disposition().unwrap().unwrap().name.unwrap(), "a b");
+        assert_eq!(&*parts[1].body, b"line\r\n");
+        assert_eq!(parts[1].header("x-custom"), Some("1"));
+    }
+
+    #[test]
+    fn non_ascii_filename_is_written_as_utf8() {
+        let part = OutgoingPart::form_file("f", "\u{20ac}.txt", "text/plain", "x");
+        let cd = part.content_disposition.clone().unwrap();
+        assert_eq!(cd.to_header_value().unwrap(), "form-data; name=\"f\"; filename=\"\u{20ac}.txt\"");
+    }
+
+    #[test]
+    fn boundary_collisions() {
+        let mut writer = MultipartWriter::new("mixed");
+        writer.set_boundary("abc").unwrap();
+        writer.add_part(OutgoingPart::new("xx--abcxx"));
+        assert!(matches!(writer.to_bytes(), Err(MultipartWriteError::BoundaryCollision)));
+        let mut writer = MultipartWriter::new("mixed");
+        writer.set_boundary("abc").unwrap();
+        writer.add_part(OutgoingPart::new("x").with_header("X-Y", "abc"));
+        assert!(matches!(writer.to_bytes(), Err(MultipartWriteError::BoundaryCollision)));
+        assert!(matches!(MultipartWriter::new("mixed").set_boundary("a b "),
+                         Err(MultipartWriteError::InvalidBoundary(BoundaryError::EndsWithSpace))));
+    }
+
+    #[test]
+    fn invalid_subtypes_are_rejected() {
+        for subtype in ["mixed\r\nX-Evil: 1", "", "mixed; a=b", "a/b"].iter() {
+            let mut writer = MultipartWriter::new(*subtype);
+            writer.add_part(OutgoingPart::new("x"));
+            let mut out = Vec::new();
+            assert!(matches!(writer.write_to(&mut out), Err(MultipartWriteError::InvalidSubtype)));
+            assert!(out.is_empty());
+        }
+        assert!(MultipartWriter::new("x-custom.1").to_bytes().is_ok());
+    }
+
+    #[test]
+    fn invalid_headers_are_rejected() {
+        let mut writer = MultipartWriter::new("mixed");
+        writer.add_part(OutgoingPart::new("x").with_header("X-Y", "a\r\nBcc: b"));
+        assert!(matches!(writer.to_bytes(), Err(MultipartWriteError::InvalidHeader(FoldingError::LineBreakInValue))));
+        let mut writer = MultipartWriter::new("mixed");
+        writer.add_part(OutgoingPart::form_file("f", "a\r\nb.txt", "text/plain", "x"));
+        let body = String::from_utf8(writer.to_bytes().unwrap().1).unwrap();
+        assert!(body.contains("filename=\"a%0D%0Ab.txt\"\r\n"));
+        let mut writer = MultipartWriter::new("mixed");
+        writer.add_part(OutgoingPart::new("x").with_header("X Y", "a"));
+        assert!(matches!(writer.to_bytes(), Err(MultipartWriteError::InvalidHeader(FoldingError::InvalidFieldName))));
+    }
+
+    #[test]
+    fn find_subslice() {
+        assert_eq!(find(b"abcabc", b"bc", 0), Some(1));
+        assert_eq!(find(b"abcabc", b"bc", 2), Some(4));
+        assert_eq!(find(b"abcab", b"bc", 2), None);
+        assert_eq!(find(b"ab", b"abc", 0), None);
+    }
+}
@@ -1,11 +1,20 @@
 use penc::EncodeSet;
 
-use lookup_tables::{MediaTypeChars, Token, HttpToken};
+use lookup_tables::{
+    MediaTypeChars,
+    Token, HttpToken,
+    AttrChar, AttributeChar,
+    UriUnreserved, UriPChar, UriQuery
+};
 use lut::Table;
 
 /// a percent-encoding EncodeSet for Http tokens
 ///
 /// (usable for parameter value percent encoding rfc8187)
+///
+/// Note that `'%'` is a token char and therefore is not encoded, which means
+/// encoding and then decoding a value is not lossless. Use `AttrCharEncodeSet`
+/// if this matters.
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
 pub struct HttpPercentEncodeSet;
 
@@ -19,6 +28,10 @@ impl EncodeSet for HttpPercentEncodeSet {
 /// a percent-encoding EncodeSet for Mime tokens
 ///
 /// (usable for parameter value percent encoding rfc2231)
+///
+/// Note that `'%'` is a token char and therefore is not encoded, which means
+/// encoding and then decoding a value is not lossless. Use `AttributeCharEncodeSet`
+/// if this matters.
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
 pub struct MimePercentEncodeSet;
 
@@ -29,11 +42,100 @@ impl EncodeSet for MimePercentEncodeSet {
     }
 }
 
+/// a percent-encoding EncodeSet for the `attr-char` grammar from RFC8187 (http)
+///
+/// (usable for parameter value percent encoding rfc8187, encoding and then decoding a
+/// value with it is lossless)
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct AttrCharEncodeSet;
+
+impl EncodeSet for AttrCharEncodeSet {
+    fn contains(&self, byte: u8) -> bool {
+        //true == it needs encoding
+        !MediaTypeChars::check_at(byte as usize, AttrChar)
+    }
+}
+
+/// a percent-encoding EncodeSet for the `attribute-char` grammar from RFC2231 (mime)
+///
+/// (usable for parameter value percent encoding rfc2231, encoding and then decoding a
+/// value with it is lossless)
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct AttributeCharEncodeSet;
+
+impl EncodeSet for AttributeCharEncodeSet {
+    fn contains(&self, byte: u8) -> bool {
+        //true == it needs encoding
+        !MediaTypeChars::check_at(byte as usize, AttributeChar)
+    }
+}
+
+/// a percent-encoding EncodeSet encoding all chars except `unreserved` ones (RFC3986)
+///
+/// (usable for encoding any uri component, e.g. a single path segment or query parameter)
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct UriUnreservedEncodeSet;
+
+impl EncodeSet for UriUnreservedEncodeSet {
+    fn contains(&self, byte: u8) -> bool {
+        //true == it needs encoding
+        !MediaTypeChars::check_at(byte as usize, UriUnreserved)
+    }
+}
+
+/// a percent-encoding EncodeSet for uri path segments (RFC3986 `segment`)
+///
+/// Encodes any char which is not a `pchar` and `'%'` (so that encoding is lossless).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct UriPathSegmentEncodeSet;
+
+impl EncodeSet for UriPathSegmentEncodeSet {
+    fn contains(&self, byte: u8) -> bool {
+        //true == it needs encoding
+        byte == b'%' || !MediaTypeChars::check_at(byte as usize, UriPChar)
+    }
+}
+
+/// a percent-encoding EncodeSet for uri queries and fragments (RFC3986 `query`/`fragment`)
+///
+/// Encodes any char which can not appear in a query/fragment and `'%'` (so that encoding
+/// is lossless).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct UriQueryEncodeSet;
+
+impl EncodeSet for UriQueryEncodeSet {
+    fn contains(&self, byte: u8) -> bool {
+        //true == it needs encoding
+        byte == b'%' || !MediaTypeChars::check_at(byte as usize, UriQuery)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::borrow::Cow;
-    use penc::percent_encode;
-    use super::{HttpPercentEncodeSet, MimePercentEncodeSet};
+    use penc::{percent_encode, percent_decode, EncodeSet};
+    use super::{
+        HttpPercentEncodeSet, MimePercentEncodeSet,
+        AttrCharEncodeSet, AttributeCharEncodeSet,
+        UriUnreservedEncodeSet, UriPathSegmentEncodeSet, UriQueryEncodeSet
+    };
+
+    fn assert_round_trip<E: EncodeSet>(set: E, allowed_unencoded: &[u8]) {
+        for byte in 0..=255u8 {
+            let input = [b'a', byte, b'b'];
+            let encoded = percent_encode(&input, set.clone()).to_string();
+            let decoded: Vec<u8> = percent_decode(encoded.as_bytes()).collect();
+            assert_eq!(decoded, input, "0x{:02x}", byte);
+
+            let expect_unencoded = allowed_unencoded.contains(&byte);
+            assert_eq!(encoded.len() == 3, expect_unencoded, "0x{:02x}", byte);
+        }
+
+        let all = (0..=255u8).collect::<Vec<_>>();
+        let encoded = percent_encode(&all, set).to_string();
+        let decoded: Vec<u8> = percent_decode(encoded.as_bytes()).collect();
+        assert_eq!(decoded, all);
+    }
 
     #[test]
     fn what_to_encode_and_what_not_is_not_switched_around() {
@@ -44,4 +146,53 @@ mod test {
         assert_eq!(&*res, "a%00b");
 
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn attr_char_encoding_round_trips() {
+        let attr_char = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789!#$&+-.^_`|~";
+        assert_round_trip(AttrCharEncodeSet, attr_char);
+    }
+
+    #[test]
+    fn attribute_char_encoding_round_trips() {
+        let attribute_char = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789!#$&+-.^_`{|}~";
+        assert_round_trip(AttributeCharEncodeSet, attribute_char);
+    }
+
+    #[test]
+    fn percent_star_and_quote_are_encoded() {
+        let input = "100%'*";
+        let res: Cow<str> = percent_encode(input.as_bytes(), AttrCharEncodeSet).into();
+        assert_eq!(&*res, "100%25%27%2A");
+        let res: Cow<str> = percent_encode(input.as_bytes(), AttributeCharEncodeSet).into();
+        assert_eq!(&*res, "100%25%27%2A");
+    }
+
+    const UNRESERVED: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789-._~";
+
+    #[test]
+    fn uri_unreserved_encoding_round_trips() {
+        assert_round_trip(UriUnreservedEncodeSet, UNRESERVED);
+    }
+
+    #[test]
+    fn uri_path_segment_encoding_round_trips() {
+        let pchar = [UNRESERVED, b"!$&'()*+,;=:@"].concat();
+        assert_round_trip(UriPathSegmentEncodeSet, &pchar);
+    }
+
+    #[test]
+    fn uri_query_encoding_round_trips() {
+        let query = [UNRESERVED, b"!$&'()*+,;=:@/?"].concat();
+        assert_round_trip(UriQueryEncodeSet, &query);
+    }
+
+    #[test]
+    fn uri_delimiters_are_encoded_where_needed() {
+        let input = "a/b?c#d e%";
+        let res: Cow<str> = percent_encode(input.as_bytes(), UriPathSegmentEncodeSet).into();
+        assert_eq!(&*res, "a%2Fb%3Fc%23d%20e%25");
+        let res: Cow<str> = percent_encode(input.as_bytes(), UriQueryEncodeSet).into();
+        assert_eq!(&*res, "a/b?c%23d%20e%25");
+    }
+}
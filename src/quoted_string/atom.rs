@@ -0,0 +1,301 @@
+use lut::{Table};
+use lookup_tables::{
+    MediaTypeChars,
+    AText, Ws
+};
+use qs::spec::{
+    PartialCodePoint,
+    WithoutQuotingValidator,
+};
+
+use super::Utf8Decoder;
+
+/// returns true if the (us-ascii) char is `atext`
+#[inline]
+fn is_atext(iu8: u8) -> bool {
+    MediaTypeChars::check_at(iu8 as usize, AText)
+}
+
+/// returns true if the char has to be passed to the utf-8 decoder, i.e. it is
+/// non us-ascii or the decoder is in the middle of a multi-byte sequence
+#[inline]
+fn needs_decoding(utf8: bool, decoder: &Utf8Decoder, iu8: u8) -> bool {
+    utf8 && (iu8 > 0x7f || !decoder.is_complete())
+}
+
+macro_rules! def_atom_validator {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            utf8 = $utf8:tt;
+        }
+    ) => (
+        $(#[$meta])*
+        #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+        pub struct $name {
+            has_atext: bool,
+            decoder: Utf8Decoder
+        }
+
+        impl $name {
+            /// create a new validator
+            pub fn new() -> Self {
+                Default::default()
+            }
+        }
+
+        impl WithoutQuotingValidator for $name {
+            fn next(&mut self, pcp: PartialCodePoint) -> bool {
+                let iu8 = pcp.as_u8();
+                if needs_decoding($utf8, &self.decoder, iu8) {
+                    match self.decoder.next_decoder(pcp) {
+                        Ok(decoder) => self.decoder = decoder,
+                        Err(_) => return false
+                    }
+                } else if !is_atext(iu8) {
+                    return false;
+                }
+                self.has_atext = true;
+                true
+            }
+
+            fn end(&self) -> bool {
+                self.has_atext && self.decoder.is_complete()
+            }
+        }
+    );
+}
+
+def_atom_validator! {
+    /// a type providing a `WithoutQuotingValidator` for `atom`s (without the optional CFWS)
+    /// wrt. the modern, us-ascii mail grammar (RFC5322)
+    ///
+    /// Note: the `obs-` grammar does not change `atom`, so this can be used for it, too.
+    pub struct AtomValidator {
+        utf8 = false;
+    }
+}
+
+def_atom_validator! {
+    /// a type providing a `WithoutQuotingValidator` for `atom`s (without the optional CFWS)
+    /// wrt. the internationalized mail grammar (RFC6532)
+    ///
+    /// Non us-ascii chars have to be valid utf-8 (see `Utf8Decoder`).
+    pub struct AtomUtf8Validator {
+        utf8 = true;
+    }
+}
+
+/// the state of a dot-atom validator
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+enum DotAtomState {
+    #[default]
+    Start,
+    AText,
+    Dot,
+    /// (obs only) ws after a atext
+    WsAfterAText,
+    /// (obs only) ws after a dot
+    WsAfterDot
+}
+
+impl DotAtomState {
+    /// `is_atext` is passed in as non us-ascii chars are atext in the utf8 grammar
+    fn advance(self, iu8: u8, is_atext: bool, obs: bool) -> Option<Self> {
+        use self::DotAtomState::*;
+        let is_ws = obs && MediaTypeChars::check_at(iu8 as usize, Ws);
+        match self {
+            Start | Dot | WsAfterDot | AText if is_atext => Some(AText),
+            AText | WsAfterAText if iu8 == b'.' => Some(Dot),
+            AText | WsAfterAText if is_ws => Some(WsAfterAText),
+            Dot | WsAfterDot if is_ws => Some(WsAfterDot),
+            _ => None
+        }
+    }
+}
+
+macro_rules! def_dot_atom_validator {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            utf8 = $utf8:tt;
+            obsolete_syntax = $obs:tt;
+        }
+    ) => (
+        $(#[$meta])*
+        #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+        pub struct $name {
+            state: DotAtomState,
+            decoder: Utf8Decoder
+        }
+
+        impl $name {
+            /// create a new validator
+            pub fn new() -> Self {
+                Default::default()
+            }
+        }
+
+        impl WithoutQuotingValidator for $name {
+            fn next(&mut self, pcp: PartialCodePoint) -> bool {
+                let iu8 = pcp.as_u8();
+                if needs_decoding($utf8, &self.decoder, iu8) {
+                    let decoder = match self.decoder.next_decoder(pcp) {
+                        Ok(decoder) => decoder,
+                        Err(_) => return false
+                    };
+                    // only the first byte of a code point changes the state
+                    if self.decoder.is_complete() {
+                        match self.state.advance(iu8, true, $obs) {
+                            Some(state) => self.state = state,
+                            None => return false
+                        }
+                    }
+                    self.decoder = decoder;
+                    return true;
+                }
+                match self.state.advance(iu8, is_atext(iu8), $obs) {
+                    Some(state) => {
+                        self.state = state;
+                        true
+                    },
+                    None => false
+                }
+            }
+
+            fn end(&self) -> bool {
+                self.state == DotAtomState::AText && self.decoder.is_complete()
+            }
+        }
+    );
+}
+
+def_dot_atom_validator! {
+    /// a type providing a `WithoutQuotingValidator` for `dot-atom-text` wrt. the modern,
+    /// us-ascii mail grammar (RFC5322)
+    ///
+    /// i.e. one or more `atom`s separated by a single `'.'` each, e.g. the local-part of
+    /// `john.doe@example.com`
+    pub struct DotAtomValidator {
+        utf8 = false;
+        obsolete_syntax = false;
+    }
+}
+
+def_dot_atom_validator! {
+    /// a type providing a `WithoutQuotingValidator` for `dot-atom-text` wrt. the
+    /// internationalized, modern mail grammar (RFC6532)
+    ///
+    /// Non us-ascii chars have to be valid utf-8 (see `Utf8Decoder`).
+    pub struct DotAtomUtf8Validator {
+        utf8 = true;
+        obsolete_syntax = false;
+    }
+}
+
+def_dot_atom_validator! {
+    /// a type providing a `WithoutQuotingValidator` for `dot-atom-text` wrt. the obs mail grammar
+    ///
+    /// Like in `obs-local-part`/`obs-domain` ws is allowed around the `'.'`s (e.g. `john . doe`),
+    /// comments and quoted-strings are not supported.
+    pub struct DotAtomObsValidator {
+        utf8 = false;
+        obsolete_syntax = true;
+    }
+}
+
+def_dot_atom_validator! {
+    /// a type providing a `WithoutQuotingValidator` for `dot-atom-text` wrt. the
+    /// internationalized, obs mail grammar
+    ///
+    /// see `DotAtomObsValidator`
+    pub struct DotAtomObsUtf8Validator {
+        utf8 = true;
+        obsolete_syntax = true;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use lut::{Table, Any};
+    use lookup_tables::{MediaTypeChars, AText, Specials, VChar};
+    use qs::spec::{WithoutQuotingValidator, PartialCodePoint};
+    use super::*;
+
+    fn validate<V: WithoutQuotingValidator>(mut vali: V, input: &str) -> bool {
+        input.bytes().all(|bch| vali.next(PartialCodePoint::from_utf8_byte(bch))) && vali.end()
+    }
+
+    #[test]
+    fn atext_and_specials_are_vchar() {
+        for idx in 0..256 {
+            let vchar = MediaTypeChars::check_at(idx, VChar);
+            let both = MediaTypeChars::check_at(idx, Any::new(AText) | Specials);
+            assert_eq!(vchar, both, "0x{:02x}", idx);
+            assert!(!(MediaTypeChars::check_at(idx, AText) && MediaTypeChars::check_at(idx, Specials)));
+        }
+    }
+
+    #[test]
+    fn atom() {
+        for valid in &["a", "john", "a!#$%&'*+-/=?^_`{|}~9"] {
+            assert!(validate(AtomValidator::new(), valid), "{:?}", valid);
+            assert!(validate(AtomUtf8Validator::new(), valid), "{:?}", valid);
+        }
+        for invalid in &["", "a.b", "a b", "a@b", "(a)", "a\"b"] {
+            assert!(!validate(AtomValidator::new(), invalid), "{:?}", invalid);
+            assert!(!validate(AtomUtf8Validator::new(), invalid), "{:?}", invalid);
+        }
+        assert!(!validate(AtomValidator::new(), "j\u{f6}rg"));
+        assert!(validate(AtomUtf8Validator::new(), "j\u{f6}rg"));
+    }
+
+    #[test]
+    fn dot_atom() {
+        for valid in &["a", "john.doe", "a.b.c", "x+tag.y"] {
+            assert!(validate(DotAtomValidator::new(), valid), "{:?}", valid);
+            assert!(validate(DotAtomObsValidator::new(), valid), "{:?}", valid);
+        }
+        for invalid in &["", ".a", "a.", "a..b", "a b", "a@b", "."] {
+            assert!(!validate(DotAtomValidator::new(), invalid), "{:?}", invalid);
+            assert!(!validate(DotAtomObsValidator::new(), invalid), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn dot_atom_obs_allows_ws_around_dots() {
+        for input in &["john . doe", "a\t.b", "a. b", "a  .  b . c"] {
+            assert!(!validate(DotAtomValidator::new(), input), "{:?}", input);
+            assert!(validate(DotAtomObsValidator::new(), input), "{:?}", input);
+        }
+        for input in &["a b", " a", "a. ", "a . . b"] {
+            assert!(!validate(DotAtomObsValidator::new(), input), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn dot_atom_utf8() {
+        let input = "j\u{f6}rg.m\u{fc}ller";
+        assert!(!validate(DotAtomValidator::new(), input));
+        assert!(!validate(DotAtomObsValidator::new(), input));
+        assert!(validate(DotAtomUtf8Validator::new(), input));
+        assert!(validate(DotAtomObsUtf8Validator::new(), "j\u{f6}rg . m\u{fc}ller"));
+        assert!(!validate(DotAtomUtf8Validator::new(), "j\u{f6}rg . m\u{fc}ller"));
+    }
+
+    fn validate_bytes<V: WithoutQuotingValidator>(mut vali: V, input: &[u8]) -> bool {
+        input.iter().all(|&bch| vali.next(PartialCodePoint::from_utf8_byte(bch))) && vali.end()
+    }
+
+    #[test]
+    fn utf8_variants_validate_utf8() {
+        assert!(validate_bytes(AtomUtf8Validator::new(), "j\u{f6}rg".as_bytes()));
+        assert!(validate_bytes(DotAtomUtf8Validator::new(), "\u{20ac}.\u{10348}".as_bytes()));
+        assert!(validate_bytes(DotAtomObsUtf8Validator::new(), "\u{e4} . \u{e4}".as_bytes()));
+        for invalid in &[&b"j\xC3"[..], b"\xC0\xAF", b"a\x80", b"\xED\xA0\x80", b"\xC3.a"] {
+            assert!(!validate_bytes(AtomUtf8Validator::new(), invalid), "{:?}", invalid);
+            assert!(!validate_bytes(DotAtomUtf8Validator::new(), invalid), "{:?}", invalid);
+            assert!(!validate_bytes(DotAtomObsUtf8Validator::new(), invalid), "{:?}", invalid);
+        }
+    }
+}
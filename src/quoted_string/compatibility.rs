@@ -0,0 +1,229 @@
+use qs::{self, error::CoreError};
+use qs::spec::{GeneralQSSpec, ParsingImpl};
+
+use super::{
+    AnyQuoting,
+    StrictParsingImpl, NormalParsingImpl, AnyParsingImpl,
+    HttpObsParsingImpl, WhatwgParsingImpl,
+    MimeParsing, MimeParsingUtf8, MimeObsParsing, MimeObsParsingUtf8
+};
+
+/// the quoted-string grammars (`ParsingImpl`s) checked by `compatibility`
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Grammar {
+    /// `StrictParsingImpl`
+    Strict,
+    /// `NormalParsingImpl`
+    Normal,
+    /// `HttpObsParsingImpl`
+    HttpObs,
+    /// `MimeParsing`
+    Mime,
+    /// `MimeParsingUtf8`
+    MimeUtf8,
+    /// `MimeObsParsing`
+    MimeObs,
+    /// `MimeObsParsingUtf8`
+    MimeObsUtf8,
+    /// `WhatwgParsingImpl`
+    Whatwg,
+    /// `AnyParsingImpl`
+    Any
+}
+
+impl Grammar {
+
+    /// all grammars, from the most strict to the most lenient one (roughly)
+    pub const ALL: [Grammar; 9] = [
+        Grammar::Strict, Grammar::Normal, Grammar::HttpObs,
+        Grammar::Mime, Grammar::MimeUtf8, Grammar::MimeObs, Grammar::MimeObsUtf8,
+        Grammar::Whatwg, Grammar::Any
+    ];
+
+    /// the name of the `ParsingImpl` type
+    pub fn impl_name(&self) -> &'static str {
+        use self::Grammar::*;
+        match *self {
+            Strict => "StrictParsingImpl",
+            Normal => "NormalParsingImpl",
+            HttpObs => "HttpObsParsingImpl",
+            Mime => "MimeParsing",
+            MimeUtf8 => "MimeParsingUtf8",
+            MimeObs => "MimeObsParsing",
+            MimeObsUtf8 => "MimeObsParsingUtf8",
+            Whatwg => "WhatwgParsingImpl",
+            Any => "AnyParsingImpl"
+        }
+    }
+
+    /// parses the input (which has to be exactly one quoted-string) wrt. this grammar
+    ///
+    /// # Error
+    ///
+    /// the index and error at which parsing failed, if there is input after the
+    /// quoted-string the error is `CoreError::QuotedStringAlreadyEnded` at the index of it
+    pub fn check(&self, input: &str) -> CheckResult {
+        use self::Grammar::*;
+        match *self {
+            Strict => check::<StrictParsingImpl>(input),
+            Normal => check::<NormalParsingImpl>(input),
+            HttpObs => check::<HttpObsParsingImpl>(input),
+            Mime => check::<MimeParsing>(input),
+            MimeUtf8 => check::<MimeParsingUtf8>(input),
+            MimeObs => check::<MimeObsParsing>(input),
+            MimeObsUtf8 => check::<MimeObsParsingUtf8>(input),
+            Whatwg => check::<WhatwgParsingImpl>(input),
+            Any => check::<AnyParsingImpl>(input)
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct CheckSpec<Impl>(Impl);
+
+impl<Impl: ParsingImpl> GeneralQSSpec for CheckSpec<Impl> {
+    type Quoting = AnyQuoting;
+    type Parsing = Impl;
+}
+
+fn check<Impl: ParsingImpl>(input: &str) -> CheckResult {
+    let parsed = qs::parse::<CheckSpec<Impl>>(input)?;
+    if parsed.tail.is_empty() {
+        Ok(())
+    } else {
+        Err((parsed.quoted_string.len(), CoreError::QuotedStringAlreadyEnded))
+    }
+}
+
+/// the result of checking a quoted-string against one grammar, see `Grammar::check`
+pub type CheckResult = Result<(), (usize, CoreError)>;
+
+/// the result of checking a quoted-string against all grammars, see `compatibility`
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct CompatibilityReport {
+    results: Vec<(Grammar, CheckResult)>
+}
+
+impl CompatibilityReport {
+
+    /// the result for each grammar (in the order of `Grammar::ALL`)
+    pub fn results(&self) -> &[(Grammar, CheckResult)] {
+        &self.results
+    }
+
+    /// the result for the given grammar
+    pub fn result(&self, grammar: Grammar) -> CheckResult {
+        self.results.iter()
+            .find(|&&(gr, _)| gr == grammar)
+            .map(|&(_, result)| result)
+            .expect("[BUG] report contains all grammars")
+    }
+
+    /// returns true if the given grammar accepts the input
+    pub fn is_accepted_by(&self, grammar: Grammar) -> bool {
+        self.result(grammar).is_ok()
+    }
+
+    /// the grammars accepting the input
+    pub fn accepted(&self) -> Vec<Grammar> {
+        self.results.iter()
+            .filter(|&&(_, result)| result.is_ok())
+            .map(|&(grammar, _)| grammar)
+            .collect()
+    }
+
+    /// the grammars rejecting the input, with the index and error at which they failed
+    pub fn rejected(&self) -> Vec<(Grammar, usize, CoreError)> {
+        self.results.iter()
+            .filter_map(|&(grammar, result)| result.err().map(|(idx, err)| (grammar, idx, err)))
+            .collect()
+    }
+}
+
+/// checks which grammars (`ParsingImpl`s) accept the quoted-string
+///
+/// While `AnyParsingImpl` accepts anything which is compatible with at least one grammar,
+/// this reports which grammars accept it and where (and why) the others failed.
+/// The input has to be exactly one quoted-string, see `Grammar::check`.
+///
+/// # Example
+///
+/// ```
+/// use media_type_impl_utils::quoted_string::{compatibility, Grammar};
+///
+/// let report = compatibility("\"a\u{e4}b\"");
+/// assert!(report.is_accepted_by(Grammar::MimeUtf8));
+/// assert!(!report.is_accepted_by(Grammar::Strict));
+/// // fails at the (first byte of the) non us-ascii char
+/// assert_eq!(report.result(Grammar::Strict).unwrap_err().0, 2);
+/// ```
+pub fn compatibility(input: &str) -> CompatibilityReport {
+    let results = Grammar::ALL.iter()
+        .map(|&grammar| (grammar, grammar.check(input)))
+        .collect();
+    CompatibilityReport { results }
+}
+
+#[cfg(test)]
+mod test {
+    use qs::error::CoreError;
+    use super::*;
+    use super::Grammar::*;
+
+    #[test]
+    fn simple_quoted_string_is_accepted_by_all() {
+        let report = compatibility("\"abc\"");
+        assert_eq!(report.accepted(), Grammar::ALL.to_vec());
+        assert!(report.rejected().is_empty());
+    }
+
+    #[test]
+    fn quoted_pairs() {
+        let report = compatibility("\"a\\\"b\"");
+        assert!(report.is_accepted_by(Strict));
+        let report = compatibility("\"a\\bc\"");
+        assert_eq!(report.result(Strict), Err((3, CoreError::UnquoteableCharQuoted)));
+        assert!(report.is_accepted_by(Normal));
+        assert!(report.is_accepted_by(Mime));
+    }
+
+    #[test]
+    fn non_ascii() {
+        let report = compatibility("\"\u{e4}\"");
+        assert_eq!(report.accepted(), vec![HttpObs, MimeUtf8, MimeObsUtf8, Whatwg, Any]);
+        assert_eq!(report.rejected(), vec![
+            (Strict, 1, CoreError::InvalidChar),
+            (Normal, 1, CoreError::InvalidChar),
+            (Mime, 1, CoreError::InvalidChar),
+            (MimeObs, 1, CoreError::InvalidChar)
+        ]);
+    }
+
+    #[test]
+    fn whatwg_only_accepts_latin1_code_points() {
+        assert!(compatibility("\"\u{80}\u{ff}\\\u{e4}\"").is_accepted_by(Whatwg));
+        let report = compatibility("\"a\u{20ac}\"");
+        assert_eq!(report.result(Whatwg), Err((2, CoreError::InvalidChar)));
+        assert!(report.is_accepted_by(MimeUtf8));
+        let report = compatibility("\"\\\u{100}\"");
+        assert_eq!(report.result(Whatwg), Err((2, CoreError::UnquoteableCharQuoted)));
+    }
+
+    #[test]
+    fn fws_and_control_chars() {
+        let report = compatibility("\"a\r\n b\"");
+        assert_eq!(report.accepted(), vec![Mime, MimeUtf8, MimeObs, MimeObsUtf8, Any]);
+        assert_eq!(report.result(Whatwg), Err((2, CoreError::InvalidChar)));
+        let report = compatibility("\"a\u{1}\"");
+        assert_eq!(report.accepted(), vec![MimeObs, MimeObsUtf8, Any]);
+    }
+
+    #[test]
+    fn trailing_input_and_missing_quotes() {
+        let report = compatibility("\"a\"b");
+        assert_eq!(report.result(Any), Err((3, CoreError::QuotedStringAlreadyEnded)));
+        let report = compatibility("abc");
+        assert_eq!(report.result(Normal), Err((0, CoreError::DoesNotStartWithDQuotes)));
+        assert!(report.accepted().is_empty());
+    }
+}
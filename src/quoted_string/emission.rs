@@ -0,0 +1,260 @@
+use std::borrow::Cow;
+use std::error::{Error as StdError};
+use std::fmt::{self, Display};
+
+use penc::utf8_percent_encode;
+use qs::{self, error::CoreError};
+use qs::spec::{GeneralQSSpec, WithoutQuotingValidator};
+
+use percent_encoding::AttrCharEncodeSet;
+
+/// returns true for the bytes which must never be emitted as part of a header value
+///
+/// i.e. `'\r'`, `'\n'` and `'\0'`, as they can be used to inject header fields (or to
+/// truncate them) even if they are part of a quoted-pair.
+pub fn is_unsafe_for_emission(bch: u8) -> bool {
+    bch == b'\r' || bch == b'\n' || bch == b'\0'
+}
+
+/// error returned if a value contains a `'\r'`, `'\n'` or `'\0'`
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct UnsafeForEmission {
+    /// the (byte) index of the unsafe byte in the value
+    pub index: usize,
+    /// the unsafe byte
+    pub byte: u8
+}
+
+impl Display for UnsafeForEmission {
+    fn fmt(&self, fter: &mut fmt::Formatter) -> fmt::Result {
+        write!(fter, "value contains unsafe byte {:?} at index {}", self.byte as char, self.index)
+    }
+}
+
+impl StdError for UnsafeForEmission {}
+
+/// checks that the value does not contain any `'\r'`, `'\n'` or `'\0'`
+///
+/// Quoting only adds `'"'` and `'\\'`, so a value passing this check is safe for emission
+/// wrt. all quoting impls. Without the check `MimeObsQuoting`/`MimeObsUtf8Quoting` would
+/// write them as (obs) quoted-pairs and `AnyQuoting` even as they are.
+///
+/// # Error
+///
+/// returns the index and byte of the first unsafe byte
+pub fn check_emission(value: &str) -> Result<(), UnsafeForEmission> {
+    match value.bytes().position(is_unsafe_for_emission) {
+        Some(index) => Err(UnsafeForEmission { index, byte: value.as_bytes()[index] }),
+        None => Ok(())
+    }
+}
+
+/// returns true if the value is safe for emission, see `check_emission`
+pub fn is_safe_for_emission(value: &str) -> bool {
+    check_emission(value).is_ok()
+}
+
+/// error returned by the `*_for_emission` functions
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum EmissionError {
+    /// the value (or parameter name) contains a unsafe byte
+    Unsafe(UnsafeForEmission),
+    /// the value can not be quoted with the given spec
+    Quoting(CoreError)
+}
+
+impl Display for EmissionError {
+    fn fmt(&self, fter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EmissionError::Unsafe(ref err) => Display::fmt(err, fter),
+            EmissionError::Quoting(ref err) => write!(fter, "can not quote value: {}", err)
+        }
+    }
+}
+
+impl StdError for EmissionError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match *self {
+            EmissionError::Unsafe(ref err) => Some(err),
+            EmissionError::Quoting(ref err) => Some(err)
+        }
+    }
+}
+
+impl From<UnsafeForEmission> for EmissionError {
+    fn from(err: UnsafeForEmission) -> Self {
+        EmissionError::Unsafe(err)
+    }
+}
+
+/// how a parameter value which can not be safely written as token or quoted-string is written
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum EmissionMode {
+    /// return a error
+    Refuse,
+    /// write it as `name*=UTF-8''...` (RFC8187/RFC2231 `ext-value`) percent encoding
+    /// any unsafe (and any non attr-char) byte
+    ExtValue
+}
+
+/// `qs::quote` refusing values with a `'\r'`, `'\n'` or `'\0'`
+///
+/// # Error
+///
+/// if the value is not safe for emission or can not be quoted
+pub fn quote_for_emission<Spec: GeneralQSSpec>(value: &str) -> Result<String, EmissionError> {
+    check_emission(value)?;
+    qs::quote::<Spec>(value).map_err(EmissionError::Quoting)
+}
+
+/// `qs::quote_if_needed` refusing values with a `'\r'`, `'\n'` or `'\0'`
+///
+/// # Error
+///
+/// if the value is not safe for emission or can not be quoted
+pub fn quote_if_needed_for_emission<'a, Spec, V>(value: &'a str, validator: &mut V)
+    -> Result<Cow<'a, str>, EmissionError>
+    where Spec: GeneralQSSpec, V: WithoutQuotingValidator
+{
+    check_emission(value)?;
+    qs::quote_if_needed::<Spec, _>(value, validator).map_err(EmissionError::Quoting)
+}
+
+/// writes the parameter `name=value`, quoting the value if needed
+///
+/// If the value is not safe for emission or can not be quoted with `Spec` it is
+/// written depending on `mode`, i.e. either a error is returned or it is written
+/// as `ext-value` (`name*=UTF-8''...`). Obs quoted-pairs of `'\r'`, `'\n'` or `'\0'`
+/// are never written, whatever `Spec` is used.
+///
+/// # Example
+///
+/// ```
+/// extern crate quoted_string;
+/// extern crate media_type_impl_utils;
+///
+/// use quoted_string::spec::GeneralQSSpec;
+/// use media_type_impl_utils::quoted_string::{
+///     param_for_emission, EmissionMode, EmissionError,
+///     MimeObsQuoting, MimeObsParsing, MimeTokenValidator
+/// };
+///
+/// #[derive(Copy, Clone, Debug)]
+/// struct Spec;
+///
+/// impl GeneralQSSpec for Spec {
+///     type Quoting = MimeObsQuoting;
+///     type Parsing = MimeObsParsing;
+/// }
+///
+/// fn main() {
+///     let mut validator = MimeTokenValidator::new();
+///     let param = param_for_emission::<Spec, _>("a", "b c", &mut validator, EmissionMode::Refuse);
+///     assert_eq!(param.unwrap(), "a=\"b c\"");
+///
+///     let value = "x\r\nBcc: y";
+///     let param = param_for_emission::<Spec, _>("a", value, &mut validator, EmissionMode::ExtValue);
+///     assert_eq!(param.unwrap(), "a*=UTF-8''x%0D%0ABcc%3A%20y");
+///     let param = param_for_emission::<Spec, _>("a", value, &mut validator, EmissionMode::Refuse);
+///     assert!(matches!(param, Err(EmissionError::Unsafe(_))));
+/// }
+/// ```
+///
+/// # Error
+///
+/// if the name is not safe for emission or in `EmissionMode::Refuse` if the value is not
+/// safe for emission or can not be quoted
+pub fn param_for_emission<Spec, V>(name: &str, value: &str, validator: &mut V, mode: EmissionMode)
+    -> Result<String, EmissionError>
+    where Spec: GeneralQSSpec, V: WithoutQuotingValidator
+{
+    check_emission(name)?;
+    match quote_if_needed_for_emission::<Spec, V>(value, validator) {
+        Ok(quoted) => Ok(format!("{}={}", name, quoted)),
+        Err(err) => match mode {
+            EmissionMode::Refuse => Err(err),
+            EmissionMode::ExtValue => {
+                Ok(format!("{}*=UTF-8''{}", name, utf8_percent_encode(value, AttrCharEncodeSet)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use qs::{self, error::CoreError};
+    use qs::spec::GeneralQSSpec;
+    use super::super::{
+        AnyQuoting, AnyParsingImpl, MimeObsQuoting, MimeObsParsing,
+        NormalQuoting, NormalParsingImpl, MimeTokenValidator, HttpTokenValidator
+    };
+    use super::*;
+
+    #[derive(Copy, Clone, Debug)]
+    struct ObsSpec;
+
+    impl GeneralQSSpec for ObsSpec {
+        type Quoting = MimeObsQuoting;
+        type Parsing = MimeObsParsing;
+    }
+
+    #[derive(Copy, Clone, Debug)]
+    struct AnySpec;
+
+    impl GeneralQSSpec for AnySpec {
+        type Quoting = AnyQuoting;
+        type Parsing = AnyParsingImpl;
+    }
+
+    #[derive(Copy, Clone, Debug)]
+    struct NormalSpec;
+
+    impl GeneralQSSpec for NormalSpec {
+        type Quoting = NormalQuoting;
+        type Parsing = NormalParsingImpl;
+    }
+
+    #[test]
+    fn check() {
+        assert_eq!(check_emission("a\tb \"c\""), Ok(()));
+        assert_eq!(check_emission("ab\nc"), Err(UnsafeForEmission { index: 2, byte: b'\n' }));
+        assert!(!is_safe_for_emission("\0"));
+        assert!(!is_safe_for_emission("a\r"));
+    }
+
+    #[test]
+    fn plain_quoting_would_emit_unsafe_bytes() {
+        assert_eq!(qs::quote::<ObsSpec>("a\r\nb").unwrap(), "\"a\\\r\\\nb\"");
+        assert_eq!(qs::quote::<AnySpec>("a\r\nb").unwrap(), "\"a\r\nb\"");
+    }
+
+    #[test]
+    fn quote_for_emission_refuses_unsafe_bytes() {
+        let unsafe_lf = EmissionError::Unsafe(UnsafeForEmission { index: 1, byte: b'\n' });
+        assert_eq!(quote_for_emission::<ObsSpec>("a\nb"), Err(unsafe_lf));
+        assert_eq!(quote_for_emission::<AnySpec>("a\nb"), Err(unsafe_lf));
+        assert_eq!(quote_for_emission::<ObsSpec>("a\0"),
+                   Err(EmissionError::Unsafe(UnsafeForEmission { index: 1, byte: 0 })));
+        assert_eq!(quote_for_emission::<ObsSpec>("a\u{1}").unwrap(), "\"a\\\u{1}\"");
+        assert_eq!(quote_for_emission::<NormalSpec>("\u{e4}"), Err(EmissionError::Quoting(CoreError::InvalidChar)));
+
+        let mut validator = MimeTokenValidator::new();
+        assert_eq!(quote_if_needed_for_emission::<ObsSpec, _>("abc", &mut validator).unwrap(), "abc");
+        assert_eq!(quote_if_needed_for_emission::<ObsSpec, _>("a\r", &mut validator),
+                   Err(EmissionError::Unsafe(UnsafeForEmission { index: 1, byte: b'\r' })));
+    }
+
+    #[test]
+    fn params() {
+        let mut validator = HttpTokenValidator::new();
+        assert_eq!(param_for_emission::<NormalSpec, _>("a", "b", &mut validator, EmissionMode::Refuse).unwrap(),
+                   "a=b");
+        assert_eq!(param_for_emission::<NormalSpec, _>("a", "\u{e4}\0", &mut validator, EmissionMode::ExtValue)
+                       .unwrap(),
+                   "a*=UTF-8''%C3%A4%00");
+        assert_eq!(param_for_emission::<NormalSpec, _>("a", "\u{e4}", &mut validator, EmissionMode::Refuse),
+                   Err(EmissionError::Quoting(CoreError::InvalidChar)));
+        assert_eq!(param_for_emission::<NormalSpec, _>("a\r\nb", "c", &mut validator, EmissionMode::ExtValue),
+                   Err(EmissionError::Unsafe(UnsafeForEmission { index: 1, byte: b'\r' })));
+    }
+}
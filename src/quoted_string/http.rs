@@ -2,7 +2,8 @@ use lut::{Table};
 use lookup_tables::{
     MediaTypeChars,
     QTextWs,
-    HttpToken
+    HttpToken,
+    CookieOctet
 };
 use qs::error::CoreError;
 use qs::spec::{
@@ -10,6 +11,7 @@ use qs::spec::{
     ParsingImpl,
     State,
     WithoutQuotingValidator,
+    QuotingClassifier, QuotingClass,
 };
 
 /// a zero-sized type to provide a `ParsingImpl` for media types wrt. the (obs) Http grammar
@@ -35,6 +37,47 @@ impl ParsingImpl for HttpObsParsingImpl {
     }
 }
 
+/// a zero-sized type to provide a `ParsingImpl` for parameter values wrt. the WHATWG
+/// mime sniffing standard (section 4.4, "parse a MIME type")
+///
+/// The standard collects quoted-strings allowing any char to be quoted, but only keeps
+/// parameter values consisting of "HTTP quoted-string token code points" (`'\t'`, `' '`-`'~'`
+/// and `U+0080`-`U+00FF`), which is what this impl accepts.
+///
+/// The input is expected to be utf-8 (as it is for `&str`), i.e. `U+0080`-`U+00FF` are the
+/// sequences starting with `0xC2` or `0xC3`. Any other non us-ascii char is rejected at it's
+/// first byte. (The standard works on isomorphic decoded bytes, for which any non us-ascii
+/// byte would be in this range.)
+///
+/// Note that the standard also accepts unterminated quoted-strings (at the end of the
+/// input), which can not be expressed with a `ParsingImpl`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct WhatwgParsingImpl;
+
+impl WhatwgParsingImpl {
+    fn is_token_code_point(bch: PartialCodePoint) -> bool {
+        let iu8 = bch.as_u8();
+        // as the input is valid utf-8 only the lead bytes have to be checked, continuation
+        // bytes (`0x80`-`0xBF`) always follow a lead byte which already was checked
+        iu8 == b'\t' || (b' '..=b'~').contains(&iu8) || (0x80..=0xbf).contains(&iu8)
+            || iu8 == 0xc2 || iu8 == 0xc3
+    }
+}
+
+impl ParsingImpl for WhatwgParsingImpl {
+    fn can_be_quoted(bch: PartialCodePoint) -> bool {
+        Self::is_token_code_point(bch)
+    }
+
+    fn handle_normal_state(bch: PartialCodePoint) -> Result<(State<Self>, bool), CoreError> {
+        if Self::is_token_code_point(bch) {
+            Ok((State::Normal, true))
+        } else {
+            Err(CoreError::InvalidChar)
+        }
+    }
+}
+
 /// a zero-sized type to provide a `WithoutQuotingValidator` impl for tokens (http grammar)
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
 pub struct HttpTokenValidator;
@@ -54,4 +97,138 @@ impl WithoutQuotingValidator for HttpTokenValidator {
     fn end(&self) -> bool {
         true
     }
-}
\ No newline at end of file
+}
+
+/// the state of a `CookieValueValidator`
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+enum CookieValueState {
+    #[default]
+    Start,
+    Plain,
+    InDQuotes,
+    End
+}
+
+/// a type providing a `WithoutQuotingValidator` for cookie values (RFC6265)
+///
+/// A cookie value is either a sequence of `CookieOctet`s or a sequence of `CookieOctet`s
+/// surrounded by `'"'`, the `'"'` are just part of the value and no escaping is possible.
+///
+/// Note: cookie names are tokens, i.e. they can be validated with `HttpTokenValidator`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct CookieValueValidator {
+    state: CookieValueState
+}
+
+impl CookieValueValidator {
+    /// create a new CookieValueValidator
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl WithoutQuotingValidator for CookieValueValidator {
+    fn next(&mut self, pcp: PartialCodePoint) -> bool {
+        use self::CookieValueState::*;
+        let iu8 = pcp.as_u8();
+        let is_octet = MediaTypeChars::check_at(iu8 as usize, CookieOctet);
+        let next_state = match self.state {
+            Start if iu8 == b'"' => InDQuotes,
+            Start | Plain if is_octet => Plain,
+            InDQuotes if iu8 == b'"' => End,
+            InDQuotes if is_octet => InDQuotes,
+            _ => return false
+        };
+        self.state = next_state;
+        true
+    }
+
+    fn end(&self) -> bool {
+        self.state != CookieValueState::InDQuotes
+    }
+}
+
+/// a type providing a `QuotingClassifier` impl for cookie values (RFC6265)
+///
+/// As there is no escaping in cookie values any `CookieOctet` is qtext and any other
+/// char is invalid, i.e. a value containing any other char can not be send (without
+/// encoding it in some way first, e.g. percent encoding).
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct CookieValueQuoting;
+
+impl QuotingClassifier for CookieValueQuoting {
+    fn classify_for_quoting(pcp: PartialCodePoint) -> QuotingClass {
+        if MediaTypeChars::check_at(pcp.as_u8() as usize, CookieOctet) {
+            QuotingClass::QText
+        } else {
+            QuotingClass::Invalid
+        }
+    }
+}
+
+/// returns true if the value can be send as cookie value (RFC6265)
+///
+/// i.e. it only consists of `CookieOctet`s, a value surrounded by `'"'` is
+/// not treated special, use `CookieValueValidator` to check values including
+/// the optional `'"'`.
+pub fn can_send_as_cookie_value(value: &str) -> bool {
+    value.bytes().all(|bch| {
+        CookieValueQuoting::classify_for_quoting(PartialCodePoint::from_utf8_byte(bch))
+            == QuotingClass::QText
+    })
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(non_snake_case)]
+
+    mod CookieValueValidator {
+        use qs::spec::{WithoutQuotingValidator, PartialCodePoint};
+        use super::super::CookieValueValidator;
+
+        fn validate(input: &str) -> bool {
+            let mut vali = CookieValueValidator::new();
+            input.bytes().all(|bch| vali.next(PartialCodePoint::from_utf8_byte(bch))) && vali.end()
+        }
+
+        #[test]
+        fn valid_values() {
+            for value in &["", "abc", "a=b", "a!#$%&'()*+-./:<=>?@[]^_`{|}~", "\"\"", "\"abc\""] {
+                assert!(validate(value), "{:?}", value);
+            }
+        }
+
+        #[test]
+        fn invalid_values() {
+            for value in &["a b", "a,b", "a;b", "a\\b", "a\"b", "\"abc", "abc\"", "\"a\"b", "\"a b\"", "\u{e4}"] {
+                assert!(!validate(value), "{:?}", value);
+            }
+        }
+    }
+
+    mod CookieValueQuoting {
+        use qs::spec::{QuotingClassifier, QuotingClass, PartialCodePoint};
+        use super::super::{CookieValueQuoting, can_send_as_cookie_value};
+
+        #[test]
+        fn classify_for_quoting() {
+            for x in 0u8..0xff {
+                let pcp = PartialCodePoint::from_utf8_byte(x);
+                let expected = match x {
+                    b'"' | b',' | b';' | b'\\' => QuotingClass::Invalid,
+                    b'!'..=b'~' => QuotingClass::QText,
+                    _ => QuotingClass::Invalid
+                };
+                assert_eq!(CookieValueQuoting::classify_for_quoting(pcp), expected, "0x{:02x}", x);
+            }
+        }
+
+        #[test]
+        fn can_send() {
+            assert!(can_send_as_cookie_value("session=abc/123"));
+            assert!(can_send_as_cookie_value(""));
+            assert!(!can_send_as_cookie_value("a b"));
+            assert!(!can_send_as_cookie_value("\"abc\""));
+        }
+    }
+}
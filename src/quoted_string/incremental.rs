@@ -0,0 +1,447 @@
+use qs::Parsed;
+use qs::error::CoreError;
+use qs::spec::{
+    GeneralQSSpec,
+    PartialCodePoint,
+    ParsingImpl,
+    State,
+};
+
+use limits::{Limits, LimitKind, LimitExceeded, LimitedError};
+
+/// the result of feeding a chunk of input to a `QuotedStringScanner`
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ScanProgress {
+    /// the whole chunk was consumed but the quoted-string did not end yet
+    NeedsMoreInput,
+    /// the quoted-string ended, `consumed` bytes of the last chunk are part
+    /// of the quoted-string (including the closing `'"'`), the rest is not
+    Ended {
+        /// number of bytes of the last chunk which belong to the quoted-string
+        consumed: usize
+    }
+}
+
+/// a resumable scanner for quoted-strings, usable with any `ParsingImpl`
+///
+/// It works like the automaton used by `quoted_string::parse`, but it can be fed
+/// with a quoted-string split into multiple chunks, e.g. because it is received
+/// from a network connection. The chunks can be split at any byte, including
+/// in the middle of a quoted-pair, a FWS (for the mime impls) or a utf-8 code point.
+///
+/// The state of the scanner is `Copy` and can be taken out with `state` (together with
+/// `position` and `folds`) and used to resume later with `resume`. For the mime impls
+/// (`MimeParsingExt`) it includes the `FWSState`.
+///
+/// With `feed_with_limits` the length of the quoted-string and the number of folds
+/// (line breaks) in it can be limited, which is important if the chunks are received
+/// from a untrusted source, as the quoted-string could be endless otherwise.
+///
+/// # Example
+///
+/// ```
+/// use media_type_impl_utils::quoted_string::{
+///     QuotedStringScanner, ScanProgress, MimeParsing
+/// };
+///
+/// let mut scanner = QuotedStringScanner::<MimeParsing>::new();
+/// assert_eq!(scanner.feed(b"\"some\r"), Ok(ScanProgress::NeedsMoreInput));
+/// // resume later, e.g. after the next chunk was received
+/// let state = scanner.state();
+/// let mut scanner = QuotedStringScanner::<MimeParsing>::resume(state, scanner.position(), scanner.folds());
+/// assert_eq!(scanner.feed(b"\n text\"; tail"), Ok(ScanProgress::Ended { consumed: 7 }));
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct QuotedStringScanner<Impl: ParsingImpl> {
+    state: State<Impl>,
+    position: usize,
+    folds: usize
+}
+
+impl<Impl> Default for QuotedStringScanner<Impl>
+    where Impl: ParsingImpl
+{
+    fn default() -> Self {
+        QuotedStringScanner { state: State::Start, position: 0, folds: 0 }
+    }
+}
+
+impl<Impl> QuotedStringScanner<Impl>
+    where Impl: ParsingImpl
+{
+    /// create a new scanner, expecting the opening `'"'` as first input
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// resume scanning from a state previously returned by `state`
+    ///
+    /// `position` is the number of bytes already scanned, it's only used as offset
+    /// for the index returned with errors and for checking the length limit of
+    /// `feed_with_limits`. `folds` is the number of folds already scanned (see `folds`),
+    /// it's used for checking the folds limit of `feed_with_limits`.
+    pub fn resume(state: State<Impl>, position: usize, folds: usize) -> Self {
+        QuotedStringScanner { state, position, folds }
+    }
+
+    /// the current state of the scanner
+    pub fn state(&self) -> State<Impl> {
+        self.state
+    }
+
+    /// the number of bytes scanned so far (over all chunks)
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// the number of folds (i.e. `'\n'`) scanned so far (over all chunks)
+    pub fn folds(&self) -> usize {
+        self.folds
+    }
+
+    /// returns true if the end of the quoted-string was found
+    pub fn did_end(&self) -> bool {
+        self.state == State::End
+    }
+
+    /// scan the next chunk of input
+    ///
+    /// # Error
+    ///
+    /// If the input is not a valid quoted-string the error and the index of the
+    /// byte causing it is returned. The index is relative to the start of the
+    /// first chunk (i.e. it's a `position`). After a error the scanner is in the
+    /// `State::Failed` state.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<ScanProgress, (usize, CoreError)> {
+        without_limits(self.scan(chunk, &Limits::unlimited(), |_| {}))
+    }
+
+    /// scan the next chunk of input, checking the length and number of folds
+    ///
+    /// The limits are checked for the quoted-string as a whole (i.e. over all chunks),
+    /// using the `LimitKind::Length` and `LimitKind::Folds` limits (each `'\n'` is counted as fold).
+    ///
+    /// # Error
+    ///
+    /// see `feed`, additionally a `LimitExceeded` is returned at the index of the first
+    /// byte exceeding a limit, after which the scanner is in the `State::Failed` state
+    pub fn feed_with_limits(&mut self, chunk: &[u8], limits: &Limits)
+        -> Result<ScanProgress, LimitedError>
+    {
+        self.scan(chunk, limits, |_| {})
+    }
+
+    /// scan the next chunk of input, pushing the content of the quoted-string into `out`
+    ///
+    /// The content is the quoted-string without the surrounding `'"'`, with quoted-pairs
+    /// replaced by the char they represent and with not semantic chars (e.g. the CRLF of
+    /// FWS) removed.
+    ///
+    /// # Error
+    ///
+    /// see `feed`
+    pub fn feed_content(&mut self, chunk: &[u8], out: &mut Vec<u8>)
+        -> Result<ScanProgress, (usize, CoreError)>
+    {
+        without_limits(self.scan(chunk, &Limits::unlimited(), |bch| out.push(bch)))
+    }
+
+    /// call this once there is no more input
+    ///
+    /// # Error
+    ///
+    /// returns `CoreError::DoesNotEndWithDQuotes` with the current position
+    /// if the end of the quoted-string was not found
+    pub fn finish(&self) -> Result<(), (usize, CoreError)> {
+        if self.did_end() {
+            Ok(())
+        } else {
+            Err((self.position, CoreError::DoesNotEndWithDQuotes))
+        }
+    }
+
+    fn scan<F>(&mut self, chunk: &[u8], limits: &Limits, mut emit: F)
+        -> Result<ScanProgress, LimitedError>
+        where F: FnMut(u8)
+    {
+        for (idx, &bch) in chunk.iter().enumerate() {
+            if let Err(err) = self.check_limits(bch, limits) {
+                self.state = State::Failed;
+                return Err(err.into());
+            }
+            match advance(self.state, partial_code_point(bch)) {
+                Ok((state, emit_bch)) => {
+                    self.state = state;
+                    self.position += 1;
+                    if emit_bch {
+                        emit(bch);
+                    }
+                    if state == State::End {
+                        return Ok(ScanProgress::Ended { consumed: idx + 1 });
+                    }
+                },
+                Err(err) => {
+                    self.state = State::Failed;
+                    return Err(LimitedError::Syntax(self.position, err));
+                }
+            }
+        }
+        Ok(ScanProgress::NeedsMoreInput)
+    }
+
+    /// checks the limits before scanning `bch` (at `position`)
+    fn check_limits(&mut self, bch: u8, limits: &Limits) -> Result<(), LimitExceeded> {
+        limits.check(LimitKind::Length, self.position + 1, self.position)?;
+        if bch == b'\n' {
+            self.folds += 1;
+            limits.check(LimitKind::Folds, self.folds, self.position)?;
+        }
+        Ok(())
+    }
+}
+
+fn without_limits(res: Result<ScanProgress, LimitedError>) -> Result<ScanProgress, (usize, CoreError)> {
+    res.map_err(|err| match err {
+        LimitedError::Syntax(index, err) => (index, err),
+        LimitedError::Limit(_) => unreachable!("[BUG] limit exceeded without limits")
+    })
+}
+
+/// like `quoted_string::parse` but checking the length and number of folds of the quoted-string
+///
+/// The limits apply to the quoted-string only (not to the tail), so a hostile quoted-string
+/// is rejected once it exceeds them without scanning the rest of it.
+///
+/// # Example
+///
+/// ```
+/// extern crate quoted_string;
+/// extern crate media_type_impl_utils;
+///
+/// use quoted_string::spec::GeneralQSSpec;
+/// use media_type_impl_utils::limits::{Limits, LimitedError, LimitKind};
+/// use media_type_impl_utils::quoted_string::{parse_with_limits, MimeObsQuoting, MimeParsing};
+///
+/// #[derive(Copy, Clone, Debug)]
+/// struct Spec;
+///
+/// impl GeneralQSSpec for Spec {
+///     type Quoting = MimeObsQuoting;
+///     type Parsing = MimeParsing;
+/// }
+///
+/// fn main() {
+///     let parsed = parse_with_limits::<Spec>("\"a b\"; c", &Limits::new()).unwrap();
+///     assert_eq!(parsed.quoted_string, "\"a b\"");
+///     assert_eq!(parsed.tail, "; c");
+///
+///     match parse_with_limits::<Spec>("\"abcd\"", &Limits::new().with_max_length(4)) {
+///         Err(LimitedError::Limit(err)) => assert_eq!((err.kind, err.index), (LimitKind::Length, 4)),
+///         other => panic!("unexpected result: {:?}", other)
+///     }
+/// }
+/// ```
+///
+/// # Error
+///
+/// like `quoted_string::parse`, additionally a `LimitExceeded` is returned at the index of
+/// the first byte exceeding a limit
+pub fn parse_with_limits<'a, Spec: GeneralQSSpec>(input: &'a str, limits: &Limits)
+    -> Result<Parsed<'a>, LimitedError>
+{
+    let mut scanner = QuotedStringScanner::<Spec::Parsing>::new();
+    match scanner.feed_with_limits(input.as_bytes(), limits)? {
+        ScanProgress::Ended { consumed } => Ok(Parsed {
+            quoted_string: &input[..consumed],
+            tail: &input[consumed..]
+        }),
+        ScanProgress::NeedsMoreInput =>
+            Err(LimitedError::Syntax(input.len(), CoreError::DoesNotEndWithDQuotes))
+    }
+}
+
+/// `PartialCodePoint::from_utf8_byte` (debug) asserts the byte is not 0xFF, but with raw
+/// input it can be. It can not be mapped to 0xFF as `PartialCodePoint::from_code_point` uses
+/// that for (complete) non us-ascii code points, so it's mapped to 0xFE which is invalid in
+/// utf-8, too (the non validating impls treat all bytes > 0x7f the same anyway)
+fn partial_code_point(bch: u8) -> PartialCodePoint {
+    if bch == 0xFF {
+        PartialCodePoint::from_utf8_byte(0xFE)
+    } else {
+        PartialCodePoint::from_utf8_byte(bch)
+    }
+}
+
+/// the state transitions of the quoted-string automaton (same as in `quoted_string`)
+fn advance<Impl: ParsingImpl>(state: State<Impl>, pcp: PartialCodePoint)
+    -> Result<(State<Impl>, bool), CoreError>
+{
+    use self::State::*;
+    let iu8 = pcp.as_u8();
+    match state {
+        Start => {
+            if iu8 == b'"' {
+                Ok((Normal, false))
+            } else {
+                Err(CoreError::DoesNotStartWithDQuotes)
+            }
+        },
+        Normal => {
+            match iu8 {
+                b'"' => Ok((End, false)),
+                b'\\' => Ok((QPStart, false)),
+                _ => Impl::handle_normal_state(pcp)
+            }
+        },
+        QPStart => {
+            if Impl::can_be_quoted(pcp) {
+                Ok((Normal, true))
+            } else {
+                Err(CoreError::UnquoteableCharQuoted)
+            }
+        },
+        Custom(inner) => inner.advance(pcp),
+        End => Err(CoreError::QuotedStringAlreadyEnded),
+        Failed => Err(CoreError::AdvancedFailedAutomaton)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use qs::error::CoreError;
+    use qs::spec::State;
+    use super::super::{
+        MimeParsing, MimeObsParsing, NormalParsingImpl, FWSState, MimeParsingExt
+    };
+    use super::*;
+
+    fn scan_in_chunks<Impl: ParsingImpl>(input: &[u8], split_at: usize)
+        -> Result<(usize, Vec<u8>), (usize, CoreError)>
+    {
+        let (first, second) = input.split_at(split_at);
+        let mut scanner = QuotedStringScanner::<Impl>::new();
+        let mut content = Vec::new();
+        match scanner.feed_content(first, &mut content)? {
+            ScanProgress::Ended { consumed } => return Ok((consumed, content)),
+            ScanProgress::NeedsMoreInput => {}
+        }
+        // save and resume to make sure no state is lost
+        let mut scanner = QuotedStringScanner::<Impl>::resume(scanner.state(), scanner.position(), scanner.folds());
+        match scanner.feed_content(second, &mut content)? {
+            ScanProgress::Ended { consumed } => Ok((first.len() + consumed, content)),
+            ScanProgress::NeedsMoreInput => scanner.finish().map(|_| unreachable!())
+        }
+    }
+
+    #[test]
+    fn any_split_point_gives_the_same_result() {
+        let input = b"\"ab\\\"c\r\n de\\\\\"; tail";
+        for split_at in 0..input.len() + 1 {
+            let res = scan_in_chunks::<MimeParsing>(input, split_at);
+            assert_eq!(res, Ok((14, b"ab\"c de\\".to_vec())), "split at {}", split_at);
+        }
+    }
+
+    #[test]
+    fn fws_state_is_part_of_the_state() {
+        let mut scanner = QuotedStringScanner::<MimeParsing>::new();
+        assert_eq!(scanner.feed(b"\"a\r"), Ok(ScanProgress::NeedsMoreInput));
+        let state = scanner.state();
+        assert_eq!(state, MimeParsing::custom_state(FWSState::HitCr, false).0);
+        if let State::Custom(inner) = state {
+            assert_eq!(inner.fws_state(), FWSState::HitCr);
+        }
+        assert_eq!(scanner.feed(b"\n"), Ok(ScanProgress::NeedsMoreInput));
+        assert_eq!(scanner.feed(b"x"), Err((4, CoreError::InvalidChar)));
+        assert_eq!(scanner.state(), State::Failed);
+    }
+
+    #[test]
+    fn errors_are_reported_with_total_position() {
+        let mut scanner = QuotedStringScanner::<NormalParsingImpl>::new();
+        assert_eq!(scanner.feed(b"\"abc"), Ok(ScanProgress::NeedsMoreInput));
+        assert_eq!(scanner.feed(b"de\0"), Err((6, CoreError::InvalidChar)));
+        assert_eq!(scanner.feed(b"x"), Err((6, CoreError::AdvancedFailedAutomaton)));
+    }
+
+    #[test]
+    fn missing_end_is_reported_on_finish() {
+        let mut scanner = QuotedStringScanner::<MimeObsParsing>::new();
+        assert_eq!(scanner.feed(b"\"abc"), Ok(ScanProgress::NeedsMoreInput));
+        assert_eq!(scanner.finish(), Err((4, CoreError::DoesNotEndWithDQuotes)));
+    }
+
+    #[test]
+    fn missing_start_is_reported() {
+        let mut scanner = QuotedStringScanner::<NormalParsingImpl>::new();
+        assert_eq!(scanner.feed(b"abc\""), Err((0, CoreError::DoesNotStartWithDQuotes)));
+    }
+
+    #[test]
+    fn limits_are_checked_over_all_chunks() {
+        let limits = Limits::new().with_max_length(6).with_max_folds(1);
+        let mut scanner = QuotedStringScanner::<MimeParsing>::new();
+        assert_eq!(scanner.feed_with_limits(b"\"a\r\n", &limits), Ok(ScanProgress::NeedsMoreInput));
+        match scanner.feed_with_limits(b" bc\"", &limits) {
+            Err(LimitedError::Limit(err)) => {
+                assert_eq!(err.kind, LimitKind::Length);
+                assert_eq!(err.index, 6);
+            },
+            other => panic!("unexpected result: {:?}", other)
+        }
+        assert_eq!(scanner.state(), State::Failed);
+
+        let mut scanner = QuotedStringScanner::<MimeParsing>::new();
+        let res = scanner.feed_with_limits(b"\"a\r\n b\r\n c\"", &Limits::new().with_max_folds(1));
+        assert_eq!(res.map_err(|err| match err {
+            LimitedError::Limit(err) => (err.kind, err.index),
+            LimitedError::Syntax(..) => panic!("unexpected syntax error")
+        }), Err((LimitKind::Folds, 7)));
+    }
+
+    #[test]
+    fn folds_limit_is_checked_over_a_resume() {
+        let limits = Limits::new().with_max_folds(1);
+        let mut scanner = QuotedStringScanner::<MimeParsing>::new();
+        assert_eq!(scanner.feed_with_limits(b"\"a\r\n b", &limits), Ok(ScanProgress::NeedsMoreInput));
+        assert_eq!(scanner.folds(), 1);
+        let mut scanner = QuotedStringScanner::<MimeParsing>::resume(
+            scanner.state(), scanner.position(), scanner.folds());
+        match scanner.feed_with_limits(b"\r\n c\"", &limits) {
+            Err(LimitedError::Limit(err)) => assert_eq!((err.kind, err.index), (LimitKind::Folds, 7)),
+            other => panic!("unexpected result: {:?}", other)
+        }
+    }
+
+    #[test]
+    fn parse_with_limits_is_like_parse() {
+        use qs;
+        use qs::spec::GeneralQSSpec;
+        use super::super::MimeObsQuoting;
+
+        #[derive(Copy, Clone, Debug)]
+        struct MimeSpec;
+
+        impl GeneralQSSpec for MimeSpec {
+            type Quoting = MimeObsQuoting;
+            type Parsing = MimeParsing;
+        }
+
+        for input in &["\"a\\\"b\"c", "\"a\r\n b\"", "\"a", "a\"", "\"a\r\nb\""] {
+            let expected = qs::parse::<MimeSpec>(input).map_err(LimitedError::from);
+            assert_eq!(parse_with_limits::<MimeSpec>(input, &Limits::unlimited()), expected);
+        }
+        let limits = Limits::new().with_max_folds(0);
+        assert_eq!(parse_with_limits::<MimeSpec>("\"a\"\r\n\r\n", &limits).unwrap().tail, "\r\n\r\n");
+        match parse_with_limits::<MimeSpec>("\"a\r\n b\"", &limits) {
+            Err(LimitedError::Limit(err)) => assert_eq!((err.kind, err.index), (LimitKind::Folds, 3)),
+            other => panic!("unexpected result: {:?}", other)
+        }
+    }
+
+    #[test]
+    fn raw_0xff_does_not_panic() {
+        let mut scanner = QuotedStringScanner::<NormalParsingImpl>::new();
+        assert_eq!(scanner.feed(b"\"\xff\""), Err((1, CoreError::InvalidChar)));
+    }
+}
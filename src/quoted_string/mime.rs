@@ -39,6 +39,9 @@ impl WithoutQuotingValidator for MimeTokenValidator {
 
 
 /// a type providing a `QuotingClassifier` impl wrt. the obs mime grammar
+///
+/// Note that any us-ascii char including `'\r'`, `'\n'` and `'\0'` is written as
+/// quoted-pair, use `quote_for_emission` for values which are send.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
 pub struct MimeObsQuoting;
 
@@ -56,6 +59,8 @@ impl QuotingClassifier for MimeObsQuoting {
 }
 
 /// a type providing a `QuotingClassifier` impl wrt. the internationalized, obs mime grammar
+///
+/// Like `MimeObsQuoting` this writes `'\r'`, `'\n'` and `'\0'` as quoted-pairs.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
 pub struct MimeObsUtf8Quoting;
 
@@ -85,6 +90,14 @@ macro_rules! def_mime_parsing {
         $(#[$meta])*
         #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
         pub struct $name(FWSState);
+
+        impl $name {
+            /// the `FWSState` wrapped by this (custom) state
+            pub fn fws_state(&self) -> FWSState {
+                self.0
+            }
+        }
+
         impl MimeParsingExt for $name {
             const ALLOW_UTF8: bool = $utf8;
             const OBS: bool = $obs;
@@ -0,0 +1,212 @@
+use lut::{Table, Any};
+use lookup_tables::{
+    MediaTypeChars,
+    QText,
+    DQuoteOrEscape, Ws,
+};
+use qs::error::CoreError;
+use qs::spec::{
+    PartialCodePoint,
+    ParsingImpl,
+    State,
+};
+
+use super::{MimeParsingExt, FWSState, Utf8Decoder, Utf8Step};
+
+/// the custom state of the utf-8 validating mime parsing impls
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+enum FwsOrUtf8 {
+    Fws(FWSState),
+    Utf8(Utf8Decoder)
+}
+
+/// starts decoding a non us-ascii char (`bch > 0x7f`)
+fn start_utf8<Impl, F>(bch: PartialCodePoint, custom: F) -> Result<(State<Impl>, bool), CoreError>
+    where Impl: ParsingImpl, F: FnOnce(Utf8Decoder) -> Impl
+{
+    advance_utf8(Utf8Decoder::new(), bch, custom)
+}
+
+fn advance_utf8<Impl, F>(decoder: Utf8Decoder, bch: PartialCodePoint, custom: F)
+    -> Result<(State<Impl>, bool), CoreError>
+    where Impl: ParsingImpl, F: FnOnce(Utf8Decoder) -> Impl
+{
+    match decoder.advance_pcp(bch) {
+        Ok(Some(Utf8Step::Incomplete(decoder))) => Ok((State::Custom(custom(decoder)), true)),
+        Ok(Some(Utf8Step::Complete(_))) | Ok(None) => Ok((State::Normal, true)),
+        Err(_) => Err(CoreError::InvalidChar)
+    }
+}
+
+macro_rules! def_validating_mime_parsing {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            obsolete_syntax = $obs:tt;
+        }
+        fn can_be_quoted($nm:ident: PartialCodePoint) -> bool
+            $body:block
+    ) => (
+        $(#[$meta])*
+        #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+        pub struct $name(FwsOrUtf8);
+
+        impl $name {
+            /// the `FWSState` wrapped by this (custom) state, if it is in a FWS
+            pub fn fws_state(&self) -> Option<FWSState> {
+                match self.0 {
+                    FwsOrUtf8::Fws(state) => Some(state),
+                    FwsOrUtf8::Utf8(_) => None
+                }
+            }
+
+            /// the `Utf8Decoder` wrapped by this (custom) state, if it is in a utf-8 sequence
+            pub fn utf8_decoder(&self) -> Option<Utf8Decoder> {
+                match self.0 {
+                    FwsOrUtf8::Fws(_) => None,
+                    FwsOrUtf8::Utf8(decoder) => Some(decoder)
+                }
+            }
+        }
+
+        impl MimeParsingExt for $name {
+            const ALLOW_UTF8: bool = true;
+            const OBS: bool = $obs;
+
+            fn custom_state(state: FWSState, emit: bool) -> (State<Self>, bool) {
+                (State::Custom($name(FwsOrUtf8::Fws(state))), emit)
+            }
+        }
+
+        impl ParsingImpl for $name {
+            fn can_be_quoted($nm: PartialCodePoint) -> bool {
+                $body
+            }
+
+            fn handle_normal_state(bch: PartialCodePoint) -> Result<(State<Self>, bool), CoreError> {
+                if bch.as_u8() > 0x7f {
+                    start_utf8(bch, |decoder| $name(FwsOrUtf8::Utf8(decoder)))
+                } else {
+                    <Self as MimeParsingExt>::handle_normal_state(bch)
+                }
+            }
+
+            fn advance(&self, bch: PartialCodePoint) -> Result<(State<Self>, bool), CoreError> {
+                match self.0 {
+                    FwsOrUtf8::Fws(FWSState::HadFws) if bch.as_u8() > 0x7f => {
+                        start_utf8(bch, |decoder| $name(FwsOrUtf8::Utf8(decoder)))
+                    },
+                    FwsOrUtf8::Fws(state) => state.advance(bch),
+                    FwsOrUtf8::Utf8(decoder) => {
+                        advance_utf8(decoder, bch, |decoder| $name(FwsOrUtf8::Utf8(decoder)))
+                    }
+                }
+            }
+        }
+    );
+}
+
+def_validating_mime_parsing! {
+    /// like `MimeObsParsingUtf8` but validating that non us-ascii chars are valid utf-8
+    ///
+    /// Truncated, overlong or otherwise invalid utf-8 sequences (see `Utf8Decoder`)
+    /// cause a `CoreError::InvalidChar` at the first byte which makes them invalid.
+    pub struct MimeObsParsingValidUtf8 {
+        obsolete_syntax = true;
+    }
+    fn can_be_quoted(bch: PartialCodePoint) -> bool {
+        // Internationalized Mail does not extend quoted-pairs just qtext ...
+        // obs syntax allows any us-ascii in quoted-pairs
+        bch.as_u8() <= 0x7f
+    }
+}
+
+def_validating_mime_parsing! {
+    /// like `MimeParsingUtf8` but validating that non us-ascii chars are valid utf-8
+    ///
+    /// Truncated, overlong or otherwise invalid utf-8 sequences (see `Utf8Decoder`)
+    /// cause a `CoreError::InvalidChar` at the first byte which makes them invalid.
+    pub struct MimeParsingValidUtf8 {
+        obsolete_syntax = false;
+    }
+    fn can_be_quoted(bch: PartialCodePoint) -> bool {
+        // Internationalized Mail does not extend quoted-pairs just qtext ...
+        let idx = bch.as_u8() as usize;
+        MediaTypeChars::check_at(idx, Any::new(Ws) | QText | DQuoteOrEscape)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use qs::{self, spec::GeneralQSSpec};
+    use qs::error::CoreError;
+    use super::super::{
+        QuotedStringScanner, ScanProgress, MimeObsUtf8Quoting, MimeParsingUtf8
+    };
+    use super::*;
+
+    fn scan<Impl: ParsingImpl>(input: &[u8]) -> Result<ScanProgress, (usize, CoreError)> {
+        QuotedStringScanner::<Impl>::new().feed(input)
+    }
+
+    #[test]
+    fn valid_utf8_is_accepted() {
+        let input = "\"\u{e4}b\u{20ac}\r\n \u{10348} c\"".as_bytes();
+        let expected = Ok(ScanProgress::Ended { consumed: input.len() });
+        assert_eq!(scan::<MimeParsingValidUtf8>(input), expected);
+        assert_eq!(scan::<MimeObsParsingValidUtf8>(b"\"\xC3\xA4\r\n\t\xC3\xA4\""),
+                   Ok(ScanProgress::Ended { consumed: 9 }));
+    }
+
+    #[test]
+    fn invalid_utf8_fails_at_exact_offset() {
+        // truncated, fails at the '"'
+        assert_eq!(scan::<MimeParsingValidUtf8>(b"\"ab\xC3\""), Err((4, CoreError::InvalidChar)));
+        // overlong, fails at the lead byte
+        assert_eq!(scan::<MimeParsingValidUtf8>(b"\"ab\xC0\xAF\""), Err((3, CoreError::InvalidChar)));
+        // overlong, fails at the first continuation byte
+        assert_eq!(scan::<MimeObsParsingValidUtf8>(b"\"\xE0\x80\xAF\""),
+                   Err((2, CoreError::InvalidChar)));
+        // surrogate
+        assert_eq!(scan::<MimeParsingValidUtf8>(b"\"\xED\xA0\x80\""), Err((2, CoreError::InvalidChar)));
+        // lone continuation byte
+        assert_eq!(scan::<MimeParsingValidUtf8>(b"\"a\x80\""), Err((2, CoreError::InvalidChar)));
+        // raw 0xFF
+        assert_eq!(scan::<MimeParsingValidUtf8>(b"\"a\xFF\""), Err((2, CoreError::InvalidChar)));
+        // directly after a FWS
+        assert_eq!(scan::<MimeParsingValidUtf8>(b"\"a\r\n \xC3a\""), Err((6, CoreError::InvalidChar)));
+    }
+
+    #[test]
+    fn non_validating_counterpart_accepts_invalid_utf8() {
+        assert_eq!(scan::<MimeParsingUtf8>(b"\"ab\xC0\xAF\""), Ok(ScanProgress::Ended { consumed: 6 }));
+    }
+
+    #[test]
+    fn utf8_sequence_split_over_chunks() {
+        let mut scanner = QuotedStringScanner::<MimeParsingValidUtf8>::new();
+        assert_eq!(scanner.feed(b"\"a\xE2\x82"), Ok(ScanProgress::NeedsMoreInput));
+        if let State::Custom(inner) = scanner.state() {
+            assert_eq!(inner.fws_state(), None);
+            assert!(inner.utf8_decoder().is_some());
+        } else {
+            panic!("expected custom state");
+        }
+        assert_eq!(scanner.feed(b"\xAC\""), Ok(ScanProgress::Ended { consumed: 2 }));
+    }
+
+    #[derive(Copy, Clone, Debug)]
+    struct Spec;
+
+    impl GeneralQSSpec for Spec {
+        type Quoting = MimeObsUtf8Quoting;
+        type Parsing = MimeParsingValidUtf8;
+    }
+
+    #[test]
+    fn works_with_str_based_functions() {
+        let parsed = qs::parse::<Spec>("\"r\u{e9}sum\u{e9}\"; x").unwrap();
+        assert_eq!(parsed.tail, "; x");
+        assert_eq!(qs::to_content::<Spec>("\"r\u{e9}\\\"s\"").unwrap(), "r\u{e9}\"s");
+    }
+}
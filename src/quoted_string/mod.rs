@@ -18,6 +18,24 @@ mod http;
 pub use self::http::*;
 mod mime;
 pub use self::mime::*;
+mod structured_field;
+pub use self::structured_field::*;
+mod incremental;
+pub use self::incremental::*;
+mod multipart;
+pub use self::multipart::*;
+mod atom;
+pub use self::atom::*;
+mod utf8;
+pub use self::utf8::*;
+mod mime_utf8;
+pub use self::mime_utf8::*;
+mod unicode;
+pub use self::unicode::*;
+mod compatibility;
+pub use self::compatibility::*;
+mod emission;
+pub use self::emission::*;
 
 /// This is an extension trait for implementing MediaType parsing in context of Mime
 pub trait MimeParsingExt: ParsingImpl {
@@ -51,7 +69,8 @@ pub trait MimeParsingExt: ParsingImpl {
     /// Note if `Self::ALLOW_UTF8` is set to true any `bch.as_u8() > 0x7f` will be treated
     /// as non-us-ascii utf8. This state machine does **not** validated if it is valid utf8
     /// so if it is used on a byte sequence which is not known to be a valid utf8 string it
-    /// is still necessary to validate if it is utf8 and not e.g. latin1.
+    /// is still necessary to validate if it is utf8 and not e.g. latin1 (or to use
+    /// `MimeParsingValidUtf8`/`MimeObsParsingValidUtf8` which do validate it).
     fn handle_normal_state(bch: PartialCodePoint) -> Result<(State<Self>, bool), CoreError> {
         let iu8 = bch.as_u8();
 
@@ -0,0 +1,216 @@
+use std::borrow::Cow;
+use std::error::{Error as StdError};
+use std::fmt::{self, Display};
+
+use lut::{Table};
+use lookup_tables::{
+    MediaTypeChars,
+    BChars, BCharsNoSpace,
+    Token
+};
+use qs::spec::{
+    PartialCodePoint,
+    WithoutQuotingValidator,
+};
+
+/// the maximal length of a multipart boundary (RFC2046)
+pub const MAX_BOUNDARY_LENGTH: usize = 70;
+
+/// the reason why a multipart boundary is not valid
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum BoundaryError {
+    /// the boundary is empty
+    Empty,
+    /// the boundary is longer than `MAX_BOUNDARY_LENGTH`
+    TooLong,
+    /// the boundary contains a char which is not in `BChars` at the given index
+    InvalidChar {
+        /// the (byte) index of the invalid char
+        index: usize
+    },
+    /// the boundary ends with a `' '`
+    EndsWithSpace
+}
+
+impl Display for BoundaryError {
+    fn fmt(&self, fter: &mut fmt::Formatter) -> fmt::Result {
+        use self::BoundaryError::*;
+        match *self {
+            Empty => fter.write_str("multipart boundary is empty"),
+            TooLong => write!(fter, "multipart boundary is longer than {} chars", MAX_BOUNDARY_LENGTH),
+            InvalidChar { index } => write!(fter, "multipart boundary contains invalid char at {}", index),
+            EndsWithSpace => fter.write_str("multipart boundary ends with ' '")
+        }
+    }
+}
+
+impl StdError for BoundaryError {}
+
+/// a type providing a `WithoutQuotingValidator` for multipart boundaries (RFC2046)
+///
+/// Accepts boundaries which can be used as parameter value without quoting, i.e. which
+/// are not empty and only consist of chars which are `BChars` and `Token` chars. So with
+/// `quote_if_needed` e.g. `"a=b"` or `"a b"` are quoted.
+///
+/// Note: this does not validate the boundary itself (e.g. it's length), use `validate_boundary`
+/// (or `boundary_param_value`) for this.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct MultipartBoundaryValidator {
+    non_empty: bool
+}
+
+impl MultipartBoundaryValidator {
+    /// create a new MultipartBoundaryValidator
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl WithoutQuotingValidator for MultipartBoundaryValidator {
+    fn next(&mut self, pcp: PartialCodePoint) -> bool {
+        let iu8 = pcp.as_u8() as usize;
+        if MediaTypeChars::check_at(iu8, BChars) && MediaTypeChars::check_at(iu8, Token) {
+            self.non_empty = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn end(&self) -> bool {
+        self.non_empty
+    }
+}
+
+/// checks if the boundary is a valid multipart boundary (RFC2046)
+///
+/// # Error
+///
+/// returns the first rule the boundary violates
+pub fn validate_boundary(boundary: &str) -> Result<(), BoundaryError> {
+    let bytes = boundary.as_bytes();
+    if let Some(index) = bytes.iter().position(|&bch| !MediaTypeChars::check_at(bch as usize, BChars)) {
+        return Err(BoundaryError::InvalidChar { index });
+    }
+    match bytes.last() {
+        None => Err(BoundaryError::Empty),
+        Some(_) if bytes.len() > MAX_BOUNDARY_LENGTH => Err(BoundaryError::TooLong),
+        Some(&last) if !MediaTypeChars::check_at(last as usize, BCharsNoSpace) =>
+            Err(BoundaryError::EndsWithSpace),
+        Some(_) => Ok(())
+    }
+}
+
+/// returns true if the (valid) boundary has to be quoted when used as parameter value
+///
+/// This is the case if it contains any char which is not a token char, which for
+/// `BChars` is the same for the mime and http grammar, e.g. `'='` or `'?'`.
+///
+/// # Error
+///
+/// if the boundary is not valid, see `validate_boundary`
+pub fn boundary_needs_quoting(boundary: &str) -> Result<bool, BoundaryError> {
+    validate_boundary(boundary)?;
+    Ok(boundary.bytes().any(|bch| !MediaTypeChars::check_at(bch as usize, Token)))
+}
+
+/// returns the boundary in the form it has to be used as parameter value
+///
+/// As `BChars` contains neither `'"'` nor `'\\'` quoting is just wrapping it in `'"'`.
+///
+/// # Example
+///
+/// ```
+/// use media_type_impl_utils::quoted_string::boundary_param_value;
+///
+/// assert_eq!(boundary_param_value("simple-boundary").unwrap(), "simple-boundary");
+/// assert_eq!(boundary_param_value("=_Part_0?x").unwrap(), "\"=_Part_0?x\"");
+/// assert!(boundary_param_value("ends with space ").is_err());
+/// ```
+///
+/// # Error
+///
+/// if the boundary is not valid, see `validate_boundary`
+pub fn boundary_param_value(boundary: &str) -> Result<Cow<'_, str>, BoundaryError> {
+    if boundary_needs_quoting(boundary)? {
+        Ok(Cow::Owned(format!("\"{}\"", boundary)))
+    } else {
+        Ok(Cow::Borrowed(boundary))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use qs::spec::{WithoutQuotingValidator, PartialCodePoint};
+    use super::*;
+
+    fn can_be_used_unquoted(input: &str) -> bool {
+        let mut vali = MultipartBoundaryValidator::new();
+        input.bytes().all(|bch| vali.next(PartialCodePoint::from_utf8_byte(bch))) && vali.end()
+    }
+
+    #[test]
+    fn valid_boundaries() {
+        let max_len = "a".repeat(70);
+        for boundary in &["a", "simple boundary", "=_Part_0_123.456", "'()+_,-./:=?", &max_len] {
+            assert_eq!(validate_boundary(boundary), Ok(()), "{:?}", boundary);
+        }
+    }
+
+    #[test]
+    fn invalid_boundaries() {
+        let too_long = "a".repeat(71);
+        let cases = &[
+            ("", BoundaryError::Empty),
+            (&*too_long, BoundaryError::TooLong),
+            ("abc ", BoundaryError::EndsWithSpace),
+            (" ", BoundaryError::EndsWithSpace),
+            ("ab\"c", BoundaryError::InvalidChar { index: 2 }),
+            ("ab;c", BoundaryError::InvalidChar { index: 2 }),
+            ("a\tb", BoundaryError::InvalidChar { index: 1 }),
+        ];
+        for &(boundary, err) in cases {
+            assert_eq!(validate_boundary(boundary), Err(err), "{:?}", boundary);
+        }
+    }
+
+    #[test]
+    fn quoting_decision() {
+        assert_eq!(boundary_needs_quoting("abc-123_x.y+z'"), Ok(false));
+        assert!(can_be_used_unquoted("abc-123_x.y+z'"));
+        for boundary in &["a=b", "a?b", "a b", "a/b", "a:b", "(a)", "a,b"] {
+            assert_eq!(boundary_needs_quoting(boundary), Ok(true), "{:?}", boundary);
+            assert!(!can_be_used_unquoted(boundary), "{:?}", boundary);
+        }
+        assert!(!can_be_used_unquoted(""));
+        assert!(!can_be_used_unquoted("a;b"));
+        assert_eq!(boundary_needs_quoting("a;b"), Err(BoundaryError::InvalidChar { index: 1 }));
+    }
+
+    #[test]
+    fn param_value() {
+        assert_eq!(boundary_param_value("abc").unwrap(), "abc");
+        assert_eq!(boundary_param_value("a b").unwrap(), "\"a b\"");
+        assert_eq!(boundary_param_value(""), Err(BoundaryError::Empty));
+    }
+
+    #[test]
+    fn quote_if_needed_quotes_non_token_boundaries() {
+        use qs::{self, spec::GeneralQSSpec};
+        use super::super::{MimeObsQuoting, MimeObsParsing};
+
+        #[derive(Copy, Clone, Debug)]
+        struct Spec;
+
+        impl GeneralQSSpec for Spec {
+            type Quoting = MimeObsQuoting;
+            type Parsing = MimeObsParsing;
+        }
+
+        for &(boundary, expected) in &[("abc", "abc"), ("a=b", "\"a=b\""), ("a b", "\"a b\"")] {
+            let quoted = qs::quote_if_needed::<Spec, _>(boundary, &mut MultipartBoundaryValidator::new());
+            assert_eq!(quoted.unwrap(), expected);
+            assert_eq!(boundary_param_value(boundary).unwrap(), expected);
+        }
+    }
+}
@@ -19,6 +19,8 @@ use qs::spec::{
 ///
 /// Note that because it's "catch-all" it means it supports all quircses from other impl. like
 /// e.g. FWS and Comments from the Mime impl
+///
+/// Use `compatibility` to find out which of the other impls accept a quoted-string.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
 pub struct AnyParsingImpl;
 
@@ -95,6 +97,9 @@ impl ParsingImpl for StrictParsingImpl {
 
 
 /// a type providing a "catch-all" `QuotingClassifier` impl
+///
+/// Note that everything but `'"'` and `'\\'` is written as is, including `'\r'`, `'\n'`
+/// and `'\0'`, use `quote_for_emission` for values which are send.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
 pub struct AnyQuoting;
 
@@ -129,6 +134,9 @@ impl QuotingClassifier for NormalQuoting {
 }
 
 /// a variation or `NormalUtf8Quoting` treating all non-us-ascii chars as qtext
+///
+/// This includes e.g. bidi overrides and zero-width chars, use `quote_with_policy`
+/// to reject them.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
 pub struct NormalUtf8Quoting;
 
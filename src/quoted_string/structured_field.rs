@@ -0,0 +1,223 @@
+use lut::{Table};
+use lookup_tables::{
+    MediaTypeChars,
+    SfTokenStart, SfToken,
+    SfKeyStart, SfKey,
+    SfStringChar
+};
+use qs::error::CoreError;
+use qs::spec::{
+    PartialCodePoint,
+    ParsingImpl,
+    State,
+    WithoutQuotingValidator,
+    QuotingClassifier, QuotingClass,
+};
+
+/// a type providing a `WithoutQuotingValidator` for `sf-token` wrt. the structured field grammar
+///
+/// The first char has to be `ALPHA` or `'*'` any other char `tchar`, `':'` or `'/'` (RFC8941).
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct SfTokenValidator {
+    started: bool
+}
+
+impl SfTokenValidator {
+    /// create a new SfTokenValidator
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl WithoutQuotingValidator for SfTokenValidator {
+    fn next(&mut self, pcp: PartialCodePoint) -> bool {
+        let idx = pcp.as_u8() as usize;
+        if self.started {
+            MediaTypeChars::check_at(idx, SfToken)
+        } else {
+            self.started = MediaTypeChars::check_at(idx, SfTokenStart);
+            self.started
+        }
+    }
+}
+
+/// a type providing a `WithoutQuotingValidator` for `key` wrt. the structured field grammar
+///
+/// The first char has to be `lcalpha` or `'*'` any other char `lcalpha`, `DIGIT`, `'_'`,
+/// `'-'`, `'.'` or `'*'` (RFC8941).
+///
+/// Note: a key can not be quoted, this can only be used to validate if it's valid.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct SfKeyValidator {
+    started: bool
+}
+
+impl SfKeyValidator {
+    /// create a new SfKeyValidator
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl WithoutQuotingValidator for SfKeyValidator {
+    fn next(&mut self, pcp: PartialCodePoint) -> bool {
+        let idx = pcp.as_u8() as usize;
+        if self.started {
+            MediaTypeChars::check_at(idx, SfKey)
+        } else {
+            self.started = MediaTypeChars::check_at(idx, SfKeyStart);
+            self.started
+        }
+    }
+}
+
+/// A type providing a `ParsingImpl` for `sf-string` wrt. the structured field grammar.
+///
+/// It is similar to `StrictParsingImpl` as it only allows `'"'` and `'\\'` in quoted-pairs,
+/// but it does not allow `'\t'` (RFC8941).
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct SfStringParsingImpl;
+
+impl ParsingImpl for SfStringParsingImpl {
+
+    /// only allow `'"'` and `'\\'`
+    fn can_be_quoted(bch: PartialCodePoint) -> bool {
+        let iu8 = bch.as_u8();
+        iu8 == b'"' || iu8 == b'\\'
+    }
+
+    /// any `SfStringChar` is ok, others are invalid
+    fn handle_normal_state(bch: PartialCodePoint) -> Result<(State<Self>, bool), CoreError> {
+        if MediaTypeChars::check_at(bch.as_u8() as usize, SfStringChar) {
+            Ok((State::Normal, true))
+        } else {
+            Err(CoreError::InvalidChar)
+        }
+    }
+}
+
+/// a type providing a `QuotingClassifier` impl for `sf-string` wrt. the structured field grammar
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct SfStringQuoting;
+
+impl QuotingClassifier for SfStringQuoting {
+    fn classify_for_quoting(pcp: PartialCodePoint) -> QuotingClass {
+        let iu8 = pcp.as_u8();
+        if MediaTypeChars::check_at(iu8 as usize, SfStringChar) {
+            QuotingClass::QText
+        } else if iu8 == b'"' || iu8 == b'\\' {
+            QuotingClass::NeedsQuoting
+        } else {
+            QuotingClass::Invalid
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(non_snake_case)]
+
+    use qs::spec::{WithoutQuotingValidator, PartialCodePoint};
+
+    fn validate<V: WithoutQuotingValidator>(mut vali: V, input: &str) -> bool {
+        input.bytes().all(|bch| vali.next(PartialCodePoint::from_utf8_byte(bch))) && vali.end()
+    }
+
+    mod SfTokenValidator {
+        use super::validate;
+        use super::super::SfTokenValidator;
+
+        #[test]
+        fn valid_tokens() {
+            for token in &["a", "*", "foo123/456", "text/plain", "a:b", "Abc!#$%&'*+-.^_`|~"] {
+                assert!(validate(SfTokenValidator::new(), token), "{:?}", token);
+            }
+        }
+
+        #[test]
+        fn invalid_tokens() {
+            for token in &["1abc", ":a", "/a", "a b", "a\"b", "a;b", "a,b", "a=b"] {
+                assert!(!validate(SfTokenValidator::new(), token), "{:?}", token);
+            }
+        }
+    }
+
+    mod SfKeyValidator {
+        use super::validate;
+        use super::super::SfKeyValidator;
+
+        #[test]
+        fn valid_keys() {
+            for key in &["a", "*", "a1_-.*", "*foo"] {
+                assert!(validate(SfKeyValidator::new(), key), "{:?}", key);
+            }
+        }
+
+        #[test]
+        fn invalid_keys() {
+            for key in &["A", "aB", "1a", "_a", "-a", "a/b", "a:b", "a b"] {
+                assert!(!validate(SfKeyValidator::new(), key), "{:?}", key);
+            }
+        }
+
+        #[test]
+        fn failed_next_does_not_change_state() {
+            use qs::spec::{WithoutQuotingValidator, PartialCodePoint};
+            let mut vali = SfKeyValidator::new();
+            assert!(!vali.next(PartialCodePoint::from_utf8_byte(b'1')));
+            assert!(vali.next(PartialCodePoint::from_utf8_byte(b'a')));
+        }
+    }
+
+    mod SfStringParsingImpl {
+        use qs::error::CoreError;
+        use qs::spec::{ParsingImpl, PartialCodePoint, State};
+        use super::super::SfStringParsingImpl;
+
+        #[test]
+        fn only_dquotes_and_escape_can_be_quoted() {
+            for x in 0u8..0x7f {
+                let pcp = PartialCodePoint::from_utf8_byte(x);
+                let can_be_quoted = SfStringParsingImpl::can_be_quoted(pcp);
+                assert_eq!(can_be_quoted, x == b'"' || x == b'\\');
+            }
+        }
+
+        #[test]
+        fn printable_us_ascii_is_valid() {
+            for x in b' '..b'~' + 1 {
+                if x == b'"' || x == b'\\' { continue; }
+                let pcp = PartialCodePoint::from_utf8_byte(x);
+                let res = SfStringParsingImpl::handle_normal_state(pcp);
+                assert_eq!(res, Ok((State::Normal, true)))
+            }
+        }
+
+        #[test]
+        fn tab_ctls_and_non_us_ascii_are_invalid() {
+            for x in (0u8..b' ').chain(0x7f..0xff) {
+                let pcp = PartialCodePoint::from_utf8_byte(x);
+                let res = SfStringParsingImpl::handle_normal_state(pcp);
+                assert_eq!(res, Err(CoreError::InvalidChar), "0x{:02x}", x)
+            }
+        }
+    }
+
+    mod SfStringQuoting {
+        use qs::spec::{QuotingClassifier, QuotingClass, PartialCodePoint};
+        use super::super::SfStringQuoting;
+
+        #[test]
+        fn classify_for_quoting() {
+            for x in 0u8..0xff {
+                let pcp = PartialCodePoint::from_utf8_byte(x);
+                let expected = match x {
+                    b'"' | b'\\' => QuotingClass::NeedsQuoting,
+                    b' '..=b'~' => QuotingClass::QText,
+                    _ => QuotingClass::Invalid
+                };
+                assert_eq!(SfStringQuoting::classify_for_quoting(pcp), expected, "0x{:02x}", x);
+            }
+        }
+    }
+}
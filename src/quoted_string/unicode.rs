@@ -0,0 +1,464 @@
+use std::error::{Error as StdError};
+use std::fmt::{self, Debug, Display};
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use qs::{self, error::CoreError};
+use qs::spec::{
+    GeneralQSSpec,
+    PartialCodePoint,
+    ParsingImpl,
+    State,
+};
+
+use super::{Utf8Decoder, Utf8Step, UnsafeForEmission, check_emission};
+
+/// a kind of code point which is valid utf-8 but can be used for spoofing, e.g. of filenames
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum SuspiciousKind {
+    /// C1 control characters (`U+0080`-`U+009F`)
+    C1Control,
+    /// bidirectional formatting characters, e.g. the RIGHT-TO-LEFT OVERRIDE `U+202E`
+    BidiControl,
+    /// zero-width and otherwise invisible characters, e.g. the ZERO WIDTH SPACE `U+200B`
+    ZeroWidth,
+    /// unassigned code points
+    ///
+    /// As this crate does not ship the unicode character database this only covers the
+    /// noncharacters (e.g. `U+FFFE`, `U+FDD0`) and the planes without any assigned
+    /// code point (`U+40000`-`U+DFFFF`), i.e. code points which can not become assigned
+    /// with a future unicode version (or at last will not for a long time).
+    Unassigned
+}
+
+impl SuspiciousKind {
+
+    /// returns the kind of suspicious code point `ch` is, if it is one
+    pub fn of(ch: char) -> Option<SuspiciousKind> {
+        use self::SuspiciousKind::*;
+        let cp = ch as u32;
+        match cp {
+            0x80..=0x9F => Some(C1Control),
+            0x061C | 0x200E | 0x200F | 0x202A..=0x202E | 0x2066..=0x2069 => Some(BidiControl),
+            0x00AD | 0x034F | 0x180E | 0x200B..=0x200D | 0x2060..=0x2064 | 0xFEFF
+                | 0xE0001 | 0xE0020..=0xE007F => Some(ZeroWidth),
+            0xFDD0..=0xFDEF | 0x40000..=0xDFFFF => Some(Unassigned),
+            _ if cp & 0xFFFE == 0xFFFE => Some(Unassigned),
+            _ => None
+        }
+    }
+
+    const fn bit(self) -> u8 {
+        use self::SuspiciousKind::*;
+        match self {
+            C1Control => 0b0001,
+            BidiControl => 0b0010,
+            ZeroWidth => 0b0100,
+            Unassigned => 0b1000
+        }
+    }
+}
+
+/// what a `UnicodePolicy` does with a kind of suspicious code point
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum PolicyAction {
+    /// accept it
+    Allow,
+    /// accept it, but report it
+    Flag,
+    /// reject the input
+    Reject
+}
+
+/// a suspicious code point found by `UnicodePolicy::check`
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct UnicodeFinding {
+    /// the byte index of the code point in the input
+    pub index: usize,
+    /// the code point
+    pub ch: char,
+    /// the kind of suspicious code point it is
+    pub kind: SuspiciousKind
+}
+
+impl Display for UnicodeFinding {
+    fn fmt(&self, fter: &mut fmt::Formatter) -> fmt::Result {
+        write!(fter, "suspicious code point U+{:04X} ({:?}) at {}", self.ch as u32, self.kind, self.index)
+    }
+}
+
+impl StdError for UnicodeFinding {}
+
+/// a policy for handling suspicious (but valid) non us-ascii code points
+///
+/// The utf-8 grammars (e.g. `MimeParsingUtf8`, `NormalUtf8Quoting`) treat all non us-ascii
+/// code points as qtext, which includes code points which can be used for spoofing
+/// like bidi overrides (e.g. making `"evil\u{202E}fdp.exe"` display as `"evilexe.pdf"`).
+///
+/// The policy can be checked directly using `check`, used when quoting with
+/// `quote_with_policy` or when parsing by wrapping the `ParsingImpl` in a `HardenedParsing`.
+///
+/// # Example
+///
+/// ```
+/// # extern crate media_type_impl_utils;
+/// use media_type_impl_utils::quoted_string::{UnicodePolicy, SuspiciousKind, PolicyAction};
+///
+/// # fn main() {
+/// let policy = UnicodePolicy::new()
+///     .with(SuspiciousKind::BidiControl, PolicyAction::Reject)
+///     .with(SuspiciousKind::ZeroWidth, PolicyAction::Flag);
+///
+/// assert!(policy.check("evil\u{202E}fdp.exe").is_err());
+/// let flagged = policy.check("a\u{200B}b").unwrap();
+/// assert_eq!(flagged[0].index, 1);
+/// # }
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct UnicodePolicy {
+    flag: u8,
+    reject: u8
+}
+
+impl UnicodePolicy {
+
+    /// create a policy allowing all code points
+    pub const fn new() -> Self {
+        UnicodePolicy { flag: 0, reject: 0 }
+    }
+
+    /// create a policy rejecting all kinds of suspicious code points
+    pub const fn strict() -> Self {
+        UnicodePolicy { flag: 0, reject: 0b1111 }
+    }
+
+    /// returns a policy with the action for `kind` set to `action`
+    pub const fn with(self, kind: SuspiciousKind, action: PolicyAction) -> Self {
+        let bit = kind.bit();
+        let flag = self.flag & !bit;
+        let reject = self.reject & !bit;
+        match action {
+            PolicyAction::Allow => UnicodePolicy { flag, reject },
+            PolicyAction::Flag => UnicodePolicy { flag: flag | bit, reject },
+            PolicyAction::Reject => UnicodePolicy { flag, reject: reject | bit }
+        }
+    }
+
+    /// returns the action for the given kind of suspicious code point
+    pub fn action(&self, kind: SuspiciousKind) -> PolicyAction {
+        let bit = kind.bit();
+        if self.reject & bit != 0 {
+            PolicyAction::Reject
+        } else if self.flag & bit != 0 {
+            PolicyAction::Flag
+        } else {
+            PolicyAction::Allow
+        }
+    }
+
+    /// returns the action for the given code point
+    pub fn action_for(&self, ch: char) -> PolicyAction {
+        SuspiciousKind::of(ch)
+            .map(|kind| self.action(kind))
+            .unwrap_or(PolicyAction::Allow)
+    }
+
+    /// checks the input against the policy
+    ///
+    /// Returns all flagged code points on success.
+    ///
+    /// # Error
+    ///
+    /// the first code point the policy rejects
+    pub fn check(&self, input: &str) -> Result<Vec<UnicodeFinding>, UnicodeFinding> {
+        let mut flagged = Vec::new();
+        for (index, ch) in input.char_indices() {
+            if let Some(kind) = SuspiciousKind::of(ch) {
+                let finding = UnicodeFinding { index, ch, kind };
+                match self.action(kind) {
+                    PolicyAction::Allow => {},
+                    PolicyAction::Flag => flagged.push(finding),
+                    PolicyAction::Reject => return Err(finding)
+                }
+            }
+        }
+        Ok(flagged)
+    }
+}
+
+/// a type level `UnicodePolicy`, used to configure a `HardenedParsing` impl
+///
+/// # Example
+///
+/// ```
+/// # extern crate media_type_impl_utils;
+/// use media_type_impl_utils::quoted_string::{
+///     UnicodePolicy, UnicodePolicySpec, SuspiciousKind, PolicyAction
+/// };
+///
+/// #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+/// struct NoBidi;
+///
+/// impl UnicodePolicySpec for NoBidi {
+///     const POLICY: UnicodePolicy = UnicodePolicy::new()
+///         .with(SuspiciousKind::BidiControl, PolicyAction::Reject);
+/// }
+/// # fn main() {}
+/// ```
+pub trait UnicodePolicySpec: Copy + Eq + Hash + Debug + Default {
+    /// the policy, code points it flags are accepted when parsing
+    const POLICY: UnicodePolicy;
+}
+
+/// a `UnicodePolicySpec` rejecting all kinds of suspicious code points
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct StrictUnicode;
+
+impl UnicodePolicySpec for StrictUnicode {
+    const POLICY: UnicodePolicy = UnicodePolicy::strict();
+}
+
+/// a `ParsingImpl` wrapping a utf-8 grammar (e.g. `MimeParsingUtf8`) also rejecting code
+/// points rejected by the policy `P` (with a `CoreError::InvalidChar`)
+///
+/// The non us-ascii bytes are decoded (so they have to be valid utf-8) and the error
+/// is reported at the last byte of a rejected code point.
+///
+/// Note that quoted-pairs are handled by `quoted_string` directly, but none of the utf-8
+/// grammars allows quoting non us-ascii chars and the decoder will reject the remaining
+/// (continuation) bytes if a impl does. Also functions passing non us-ascii chars as a
+/// whole (like `quoted_string::to_content`) do not provide the code point, so they will
+/// not be checked, which is fine as long as the input was parsed with this impl before.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct HardenedParsing<Impl, P> {
+    /// `None` if the inner impl is in the `Normal` state
+    inner: Option<Impl>,
+    decoder: Utf8Decoder,
+    policy: PhantomData<P>
+}
+
+impl<Impl, P> HardenedParsing<Impl, P>
+    where Impl: ParsingImpl, P: UnicodePolicySpec
+{
+    /// the (custom) state of the wrapped impl, if it is in one
+    pub fn inner(&self) -> Option<Impl> {
+        self.inner
+    }
+
+    fn advance_with(inner: Result<(State<Impl>, bool), CoreError>, decoder: Utf8Decoder, bch: PartialCodePoint)
+        -> Result<(State<Self>, bool), CoreError>
+    {
+        let (inner_state, emit) = inner?;
+        let decoder =
+            if bch.as_u8() > 0x7f || !decoder.is_complete() {
+                match decoder.advance_pcp(bch) {
+                    Ok(Some(Utf8Step::Incomplete(decoder))) => decoder,
+                    Ok(Some(Utf8Step::Complete(ch))) => {
+                        if P::POLICY.action_for(ch) == PolicyAction::Reject {
+                            return Err(CoreError::InvalidChar);
+                        }
+                        Utf8Decoder::new()
+                    },
+                    Ok(None) => Utf8Decoder::new(),
+                    Err(_) => return Err(CoreError::InvalidChar)
+                }
+            } else {
+                decoder
+            };
+
+        let state = match inner_state {
+            State::Normal if decoder.is_complete() => State::Normal,
+            State::Normal => State::Custom(HardenedParsing { inner: None, decoder, policy: PhantomData }),
+            State::Custom(inner) => State::Custom(HardenedParsing { inner: Some(inner), decoder, policy: PhantomData }),
+            // a us-ascii char in the middle of a sequence is already rejected by the decoder
+            _ if !decoder.is_complete() => return Err(CoreError::InvalidChar),
+            State::Start => State::Start,
+            State::Failed => State::Failed,
+            State::QPStart => State::QPStart,
+            State::End => State::End
+        };
+        Ok((state, emit))
+    }
+}
+
+impl<Impl, P> ParsingImpl for HardenedParsing<Impl, P>
+    where Impl: ParsingImpl, P: UnicodePolicySpec
+{
+    fn can_be_quoted(bch: PartialCodePoint) -> bool {
+        Impl::can_be_quoted(bch)
+    }
+
+    fn handle_normal_state(bch: PartialCodePoint) -> Result<(State<Self>, bool), CoreError> {
+        Self::advance_with(Impl::handle_normal_state(bch), Utf8Decoder::new(), bch)
+    }
+
+    fn advance(&self, bch: PartialCodePoint) -> Result<(State<Self>, bool), CoreError> {
+        let inner = match self.inner {
+            Some(inner) => inner.advance(bch),
+            None => Impl::handle_normal_state(bch)
+        };
+        Self::advance_with(inner, self.decoder, bch)
+    }
+}
+
+/// the error returned by `quote_with_policy`
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum PolicyQuoteError {
+    /// the policy rejected a code point
+    Rejected(UnicodeFinding),
+    /// the input can not be quoted with the given spec
+    Quoting(CoreError),
+    /// the input contains a `'\r'`, `'\n'` or `'\0'` (see `check_emission`)
+    Unsafe(UnsafeForEmission)
+}
+
+impl Display for PolicyQuoteError {
+    fn fmt(&self, fter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PolicyQuoteError::Rejected(ref finding) => Display::fmt(finding, fter),
+            PolicyQuoteError::Quoting(ref err) => write!(fter, "can not quote input: {:?}", err),
+            PolicyQuoteError::Unsafe(ref err) => Display::fmt(err, fter)
+        }
+    }
+}
+
+impl StdError for PolicyQuoteError {}
+
+/// quotes the input with `Spec` (e.g. using `NormalUtf8Quoting`) if `policy` does not reject it
+///
+/// `QuotingClassifier` impls only see non us-ascii chars as a whole (as `0xFF`), so they
+/// can not apply a policy themselves. Returns the quoted string and the flagged code points.
+/// Like `quote_for_emission` it refuses inputs containing `'\r'`, `'\n'` or `'\0'`.
+///
+/// # Error
+///
+/// if the policy rejects a code point, the input is not safe for emission or can not be quoted
+pub fn quote_with_policy<Spec: GeneralQSSpec>(input: &str, policy: &UnicodePolicy)
+    -> Result<(String, Vec<UnicodeFinding>), PolicyQuoteError>
+{
+    check_emission(input).map_err(PolicyQuoteError::Unsafe)?;
+    let flagged = policy.check(input).map_err(PolicyQuoteError::Rejected)?;
+    let quoted = qs::quote::<Spec>(input).map_err(PolicyQuoteError::Quoting)?;
+    Ok((quoted, flagged))
+}
+
+#[cfg(test)]
+mod test {
+    use qs::{self, error::CoreError};
+    use qs::spec::GeneralQSSpec;
+    use super::super::{
+        MimeParsingUtf8, MimeObsParsingUtf8, NormalUtf8Quoting, MimeObsUtf8Quoting,
+        QuotedStringScanner, ScanProgress
+    };
+    use super::*;
+
+    #[test]
+    fn classification() {
+        use super::SuspiciousKind::*;
+        assert_eq!(SuspiciousKind::of('a'), None);
+        assert_eq!(SuspiciousKind::of('\u{e4}'), None);
+        assert_eq!(SuspiciousKind::of('\u{85}'), Some(C1Control));
+        assert_eq!(SuspiciousKind::of('\u{202E}'), Some(BidiControl));
+        assert_eq!(SuspiciousKind::of('\u{2067}'), Some(BidiControl));
+        assert_eq!(SuspiciousKind::of('\u{200B}'), Some(ZeroWidth));
+        assert_eq!(SuspiciousKind::of('\u{FEFF}'), Some(ZeroWidth));
+        assert_eq!(SuspiciousKind::of('\u{FFFF}'), Some(Unassigned));
+        assert_eq!(SuspiciousKind::of('\u{1FFFE}'), Some(Unassigned));
+        assert_eq!(SuspiciousKind::of('\u{50000}'), Some(Unassigned));
+    }
+
+    #[test]
+    fn policy_actions() {
+        let policy = UnicodePolicy::new()
+            .with(SuspiciousKind::BidiControl, PolicyAction::Reject)
+            .with(SuspiciousKind::ZeroWidth, PolicyAction::Flag);
+        assert_eq!(policy.action(SuspiciousKind::C1Control), PolicyAction::Allow);
+        assert_eq!(policy.action(SuspiciousKind::ZeroWidth), PolicyAction::Flag);
+        assert_eq!(policy.with(SuspiciousKind::ZeroWidth, PolicyAction::Allow).action(SuspiciousKind::ZeroWidth),
+                   PolicyAction::Allow);
+
+        assert_eq!(policy.check("a\u{200B}b\u{85}"), Ok(vec![
+            UnicodeFinding { index: 1, ch: '\u{200B}', kind: SuspiciousKind::ZeroWidth }
+        ]));
+        assert_eq!(policy.check("ab\u{202E}fdp.exe"), Err(
+            UnicodeFinding { index: 2, ch: '\u{202E}', kind: SuspiciousKind::BidiControl }
+        ));
+        assert_eq!(UnicodePolicy::new().check("ab\u{202E}fdp.exe"), Ok(vec![]));
+    }
+
+    fn scan<Impl: ParsingImpl>(input: &[u8]) -> Result<ScanProgress, (usize, CoreError)> {
+        QuotedStringScanner::<Impl>::new().feed(input)
+    }
+
+    type Hardened = HardenedParsing<MimeParsingUtf8, StrictUnicode>;
+
+    #[test]
+    fn hardened_parsing_rejects_at_last_byte() {
+        let input = "\"\u{e4}\r\n b\u{202E}c\"".as_bytes();
+        assert_eq!(scan::<MimeParsingUtf8>(input), Ok(ScanProgress::Ended { consumed: input.len() }));
+        assert_eq!(scan::<Hardened>(input), Err((9, CoreError::InvalidChar)));
+        assert_eq!(scan::<Hardened>(b"\"a\xC2\x85\""), Err((3, CoreError::InvalidChar)));
+        // the inner impl is still used
+        assert_eq!(scan::<Hardened>(b"\"a\r\nb\""), Err((4, CoreError::InvalidChar)));
+        // and utf-8 is validated
+        assert_eq!(scan::<Hardened>(b"\"a\xC0\xAF\""), Err((2, CoreError::InvalidChar)));
+    }
+
+    #[test]
+    fn hardened_parsing_accepts_other_code_points() {
+        let input = "\"\u{e4}\r\n \u{20ac}\\\"\u{10348}\"".as_bytes();
+        assert_eq!(scan::<Hardened>(input), Ok(ScanProgress::Ended { consumed: input.len() }));
+        let input = "\"\u{e4}\r\n\t\r\n \u{202E}\"".as_bytes();
+        let ended = Ok(ScanProgress::Ended { consumed: input.len() });
+        assert_eq!(scan::<HardenedParsing<MimeObsParsingUtf8, NoC1>>(input), ended);
+    }
+
+    #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+    struct NoC1;
+
+    impl UnicodePolicySpec for NoC1 {
+        const POLICY: UnicodePolicy = UnicodePolicy::new()
+            .with(SuspiciousKind::C1Control, PolicyAction::Reject);
+    }
+
+    #[derive(Copy, Clone, Debug)]
+    struct Spec;
+
+    impl GeneralQSSpec for Spec {
+        type Quoting = NormalUtf8Quoting;
+        type Parsing = HardenedParsing<MimeParsingUtf8, StrictUnicode>;
+    }
+
+    #[test]
+    fn works_with_str_based_functions() {
+        assert!(qs::parse::<Spec>("\"r\u{e9}sum\u{e9}\"").is_ok());
+        assert_eq!(qs::parse::<Spec>("\"a\u{200D}\"").map_err(|(idx, _)| idx), Err(4));
+        assert_eq!(qs::to_content::<Spec>("\"r\u{e9}\\\"s\"").unwrap(), "r\u{e9}\"s");
+    }
+
+    #[derive(Copy, Clone, Debug)]
+    struct MimeSpec;
+
+    impl GeneralQSSpec for MimeSpec {
+        type Quoting = MimeObsUtf8Quoting;
+        type Parsing = MimeParsingUtf8;
+    }
+
+    #[test]
+    fn quoting_with_policy() {
+        let policy = UnicodePolicy::strict().with(SuspiciousKind::ZeroWidth, PolicyAction::Flag);
+        let (quoted, flagged) = quote_with_policy::<Spec>("a\"\u{200B}", &policy).unwrap();
+        assert_eq!(quoted, "\"a\\\"\u{200B}\"");
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(
+            quote_with_policy::<MimeSpec>("x\u{202E}", &policy),
+            Err(PolicyQuoteError::Rejected(UnicodeFinding {
+                index: 1, ch: '\u{202E}', kind: SuspiciousKind::BidiControl
+            }))
+        );
+        assert_eq!(quote_with_policy::<Spec>("a\u{7}", &policy),
+                   Err(PolicyQuoteError::Quoting(CoreError::InvalidChar)));
+        assert_eq!(quote_with_policy::<MimeSpec>("a\r\n", &policy),
+                   Err(PolicyQuoteError::Unsafe(UnsafeForEmission { index: 1, byte: b'\r' })));
+    }
+}
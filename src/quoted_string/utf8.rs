@@ -0,0 +1,323 @@
+use std::error::{Error as StdError};
+use std::fmt::{self, Display};
+
+use qs::spec::{
+    PartialCodePoint,
+    WithoutQuotingValidator,
+};
+
+use super::MimeTokenValidator;
+
+/// the reason a byte sequence is not valid utf-8 (RFC3629)
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Utf8Error {
+    /// a continuation byte (`0x80`-`0xBF`) without a preceding start byte
+    UnexpectedContinuation,
+    /// a start byte was not followed by enough continuation bytes
+    IncompleteSequence,
+    /// the sequence encodes a code point which has a shorter encoding
+    Overlong,
+    /// the sequence encodes a utf-16 surrogate (`U+D800`-`U+DFFF`)
+    Surrogate,
+    /// the sequence encodes a code point larger than `U+10FFFF`
+    OutOfRange,
+    /// the byte can not appear in utf-8 (`0xF8`-`0xFF`)
+    InvalidByte
+}
+
+impl Display for Utf8Error {
+    fn fmt(&self, fter: &mut fmt::Formatter) -> fmt::Result {
+        use self::Utf8Error::*;
+        fter.write_str(match *self {
+            UnexpectedContinuation => "unexpected utf-8 continuation byte",
+            IncompleteSequence => "incomplete utf-8 sequence",
+            Overlong => "overlong utf-8 sequence",
+            Surrogate => "utf-8 sequence encodes a surrogate",
+            OutOfRange => "utf-8 sequence encodes a code point larger than U+10FFFF",
+            InvalidByte => "byte can not appear in utf-8"
+        })
+    }
+}
+
+impl StdError for Utf8Error {}
+
+/// the result of feeding a byte to a `Utf8Decoder`
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Utf8Step {
+    /// the byte is part of a code point which is not yet complete, continue with this decoder
+    Incomplete(Utf8Decoder),
+    /// the byte completed a code point (for us-ascii this is the byte itself)
+    Complete(char)
+}
+
+/// a incremental utf-8 decoder validating the input wrt. RFC3629
+///
+/// It rejects overlong sequences, surrogates and code points larger than `U+10FFFF`.
+/// The decoder is `Copy` and `advance` returns the next state instead of modifying
+/// it, which makes it easy to use in `WithoutQuotingValidator` and `ParsingImpl`
+/// implementations (which must not change their state on failure).
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct Utf8Decoder {
+    code_point: u32,
+    remaining: u8,
+    /// the range of the next continuation byte (it's only restricted for the first one)
+    lower: u8,
+    upper: u8
+}
+
+impl Utf8Decoder {
+
+    /// create a new decoder
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// returns true if the decoder is not in the middle of a multi-byte sequence
+    pub fn is_complete(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// feed the next byte to the decoder
+    ///
+    /// # Error
+    ///
+    /// if the byte can not appear at this position in a valid utf-8 sequence
+    pub fn advance(self, byte: u8) -> Result<Utf8Step, Utf8Error> {
+        if self.remaining == 0 {
+            return Utf8Decoder::start(byte);
+        }
+        if byte < self.lower || byte > self.upper {
+            return Err(match (byte, self.lower, self.upper) {
+                (0x80..=0xBF, 0xA0, _) | (0x80..=0xBF, 0x90, _) => Utf8Error::Overlong,
+                (0x80..=0xBF, _, 0x9F) => Utf8Error::Surrogate,
+                (0x80..=0xBF, _, 0x8F) => Utf8Error::OutOfRange,
+                _ => Utf8Error::IncompleteSequence
+            });
+        }
+        let code_point = (self.code_point << 6) | (byte & 0x3F) as u32;
+        if self.remaining == 1 {
+            // SAFE: the ranges of the bytes make sure it's a valid char
+            let ch = ::std::char::from_u32(code_point).expect("[BUG] decoded invalid code point");
+            Ok(Utf8Step::Complete(ch))
+        } else {
+            Ok(Utf8Step::Incomplete(Utf8Decoder {
+                code_point, remaining: self.remaining - 1, lower: 0x80, upper: 0xBF
+            }))
+        }
+    }
+
+    fn start(byte: u8) -> Result<Utf8Step, Utf8Error> {
+        let (remaining, lower, upper, bits) = match byte {
+            0x00..=0x7F => return Ok(Utf8Step::Complete(byte as char)),
+            0x80..=0xBF => return Err(Utf8Error::UnexpectedContinuation),
+            0xC0 | 0xC1 => return Err(Utf8Error::Overlong),
+            0xC2..=0xDF => (1, 0x80, 0xBF, byte & 0x1F),
+            0xE0 => (2, 0xA0, 0xBF, byte & 0x0F),
+            0xED => (2, 0x80, 0x9F, byte & 0x0F),
+            0xE1..=0xEF => (2, 0x80, 0xBF, byte & 0x0F),
+            0xF0 => (3, 0x90, 0xBF, byte & 0x07),
+            0xF4 => (3, 0x80, 0x8F, byte & 0x07),
+            0xF1..=0xF3 => (3, 0x80, 0xBF, byte & 0x07),
+            0xF5..=0xF7 => return Err(Utf8Error::OutOfRange),
+            0xF8..=0xFF => return Err(Utf8Error::InvalidByte)
+        };
+        Ok(Utf8Step::Incomplete(Utf8Decoder { code_point: bits as u32, remaining, lower, upper }))
+    }
+
+    /// like `advance_pcp` but returns the decoder to continue with (a new one if the
+    /// code point was completed)
+    pub(crate) fn next_decoder(self, pcp: PartialCodePoint) -> Result<Utf8Decoder, Utf8Error> {
+        match self.advance_pcp(pcp)? {
+            Some(Utf8Step::Incomplete(decoder)) => Ok(decoder),
+            _ => Ok(Utf8Decoder::new())
+        }
+    }
+
+    /// feed a `PartialCodePoint` to the decoder
+    ///
+    /// `quoted_string::quote` and similar pass non us-ascii chars as a single
+    /// `PartialCodePoint` with the value `0xFF` (which can not appear in utf-8), such a
+    /// value is treated as a complete (valid) non us-ascii code point. Returns `Ok(None)`
+    /// for it, as the actual code point is not known.
+    pub fn advance_pcp(self, pcp: PartialCodePoint) -> Result<Option<Utf8Step>, Utf8Error> {
+        let byte = pcp.as_u8();
+        if byte == 0xFF && self.is_complete() {
+            Ok(None)
+        } else {
+            self.advance(byte).map(Some)
+        }
+    }
+}
+
+/// a `WithoutQuotingValidator` wrapping a us-ascii validator to also allow non us-ascii
+/// code points, validating that they are valid utf-8 (RFC6532, RFC3629)
+///
+/// us-ascii chars are passed to the inner validator, non us-ascii code points are
+/// checked to be valid utf-8 (rejecting overlong sequences, surrogates etc.) and are
+/// not passed to the inner validator. The input can be fed byte by byte (using
+/// `PartialCodePoint::from_utf8_byte`) or char by char (using `from_code_point`, as done
+/// by `quoted_string::quote_if_needed`).
+///
+/// **The inner validator only sees the us-ascii chars**, so rules of it wrt. the position
+/// or number of chars apply to the us-ascii chars only. E.g. with `StrictTokenValidator`
+/// `"\u{e4}abc"` is accepted even though the first char is not alphanumeric and the
+/// length limit (127) only counts us-ascii chars. Such rules have to be checked separately
+/// if they should apply to non us-ascii chars, too.
+///
+/// `end` returns false if the input ends in the middle of a multi-byte sequence.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct Utf8Validator<V> {
+    inner: V,
+    decoder: Utf8Decoder
+}
+
+impl<V> Utf8Validator<V>
+    where V: WithoutQuotingValidator
+{
+    /// wrap the given us-ascii validator
+    pub fn new(inner: V) -> Self {
+        Utf8Validator { inner, decoder: Utf8Decoder::new() }
+    }
+}
+
+impl<V> WithoutQuotingValidator for Utf8Validator<V>
+    where V: WithoutQuotingValidator
+{
+    fn next(&mut self, pcp: PartialCodePoint) -> bool {
+        match self.decoder.advance_pcp(pcp) {
+            Ok(Some(Utf8Step::Complete(ch))) if (ch as u32) < 0x80 => self.inner.next(pcp),
+            Ok(Some(Utf8Step::Complete(_))) | Ok(None) => {
+                self.decoder = Utf8Decoder::new();
+                true
+            },
+            Ok(Some(Utf8Step::Incomplete(decoder))) => {
+                self.decoder = decoder;
+                true
+            },
+            Err(_) => false
+        }
+    }
+
+    fn end(&self) -> bool {
+        self.decoder.is_complete() && self.inner.end()
+    }
+}
+
+/// a `WithoutQuotingValidator` for tokens wrt. the internationalized mime grammar
+///
+/// i.e. `MimeTokenValidator` extended with (valid) utf-8 non us-ascii code points (RFC6532),
+/// it can be created with `MimeTokenUtf8Validator::default()`
+pub type MimeTokenUtf8Validator = Utf8Validator<MimeTokenValidator>;
+
+#[cfg(test)]
+mod test {
+    use qs::{self, spec::{GeneralQSSpec, WithoutQuotingValidator, PartialCodePoint}};
+    use std::borrow::Cow;
+    use super::super::{MimeObsUtf8Quoting, MimeParsingUtf8, HttpTokenValidator};
+    use super::*;
+
+    fn decode(input: &[u8]) -> Result<String, (usize, Utf8Error)> {
+        let mut decoder = Utf8Decoder::new();
+        let mut out = String::new();
+        for (idx, &bch) in input.iter().enumerate() {
+            match decoder.advance(bch).map_err(|err| (idx, err))? {
+                Utf8Step::Incomplete(next) => decoder = next,
+                Utf8Step::Complete(ch) => {
+                    decoder = Utf8Decoder::new();
+                    out.push(ch)
+                }
+            }
+        }
+        if decoder.is_complete() {
+            Ok(out)
+        } else {
+            Err((input.len(), Utf8Error::IncompleteSequence))
+        }
+    }
+
+    fn validate_bytes<V: WithoutQuotingValidator>(mut vali: V, input: &[u8]) -> bool {
+        input.iter().all(|&bch| vali.next(PartialCodePoint::from_utf8_byte(bch))) && vali.end()
+    }
+
+    #[test]
+    fn decodes_valid_utf8() {
+        let input = "a\u{e4}\u{20ac}\u{10348}\u{10FFFF}\u{7ff}\u{800}\u{ffff}\u{10000}";
+        assert_eq!(decode(input.as_bytes()), Ok(input.to_owned()));
+    }
+
+    #[test]
+    fn agrees_with_std_for_all_two_and_three_byte_sequences() {
+        for first in 0x80..=0xFFu8 {
+            for second in 0..=0xFFu8 {
+                let input = [first, second, 0x80];
+                let expected = ::std::str::from_utf8(&input).is_ok()
+                    || ::std::str::from_utf8(&input[..2]).is_ok();
+                let decoded = decode(&input).is_ok() || decode(&input[..2]).is_ok();
+                assert_eq!(decoded, expected, "{:02x} {:02x}", first, second);
+            }
+        }
+    }
+
+    #[test]
+    fn error_reasons() {
+        assert_eq!(decode(b"a\x80"), Err((1, Utf8Error::UnexpectedContinuation)));
+        assert_eq!(decode(b"\xC0\xAF"), Err((0, Utf8Error::Overlong)));
+        assert_eq!(decode(b"\xE0\x80\xAF"), Err((1, Utf8Error::Overlong)));
+        assert_eq!(decode(b"\xF0\x80\x80\xAF"), Err((1, Utf8Error::Overlong)));
+        assert_eq!(decode(b"\xED\xA0\x80"), Err((1, Utf8Error::Surrogate)));
+        assert_eq!(decode(b"\xF4\x90\x80\x80"), Err((1, Utf8Error::OutOfRange)));
+        assert_eq!(decode(b"\xF5\x80\x80\x80"), Err((0, Utf8Error::OutOfRange)));
+        assert_eq!(decode(b"\xFE"), Err((0, Utf8Error::InvalidByte)));
+        assert_eq!(decode(b"\xC3a"), Err((1, Utf8Error::IncompleteSequence)));
+        assert_eq!(decode(b"\xE2\x82"), Err((2, Utf8Error::IncompleteSequence)));
+    }
+
+    #[test]
+    fn mime_token_utf8_validator_with_bytes() {
+        assert!(validate_bytes(MimeTokenUtf8Validator::default(), "m\u{e4}x{1}".as_bytes()));
+        assert!(!validate_bytes(MimeTokenUtf8Validator::default(), b"m\xC3"));
+        assert!(!validate_bytes(MimeTokenUtf8Validator::default(), b"m\xC0\xAFx"));
+        assert!(!validate_bytes(MimeTokenUtf8Validator::default(), b"m\xED\xA0\x80"));
+        assert!(!validate_bytes(MimeTokenUtf8Validator::default(), "m\u{e4} x".as_bytes()));
+    }
+
+    #[test]
+    fn inner_validator_only_sees_us_ascii() {
+        use super::super::StrictTokenValidator;
+        let make = || Utf8Validator::new(StrictTokenValidator::default());
+        assert!(!validate_bytes(StrictTokenValidator::default(), b"-abc"));
+        assert!(validate_bytes(make(), "\u{e4}abc".as_bytes()));
+        assert!(validate_bytes(make(), "\u{e4}".repeat(200).as_bytes()));
+        assert!(!validate_bytes(make(), "a".repeat(128).as_bytes()));
+    }
+
+    #[test]
+    fn failed_next_does_not_change_state() {
+        let mut vali = MimeTokenUtf8Validator::default();
+        assert!(vali.next(PartialCodePoint::from_utf8_byte(0xE2)));
+        assert!(!vali.next(PartialCodePoint::from_utf8_byte(b'a')));
+        assert!(vali.next(PartialCodePoint::from_utf8_byte(0x82)));
+        assert!(vali.next(PartialCodePoint::from_utf8_byte(0xAC)));
+        assert!(vali.end());
+    }
+
+    #[derive(Copy, Clone, Debug)]
+    struct MimeUtf8Spec;
+
+    impl GeneralQSSpec for MimeUtf8Spec {
+        type Quoting = MimeObsUtf8Quoting;
+        type Parsing = MimeParsingUtf8;
+    }
+
+    #[test]
+    fn works_with_quote_if_needed() {
+        let mut vali = Utf8Validator::new(HttpTokenValidator::new());
+        let res = qs::quote_if_needed::<MimeUtf8Spec, _>("r\u{e9}sum\u{e9}", &mut vali).unwrap();
+        assert_eq!(res, Cow::Borrowed("r\u{e9}sum\u{e9}"));
+
+        let mut vali = MimeTokenUtf8Validator::default();
+        let res = qs::quote_if_needed::<MimeUtf8Spec, _>("r\u{e9} sum\u{e9}", &mut vali).unwrap();
+        let expected: Cow<str> = Cow::Owned("\"r\u{e9} sum\u{e9}\"".into());
+        assert_eq!(res, expected);
+    }
+}
@@ -0,0 +1,367 @@
+use std::sync::OnceLock;
+
+use lut::Access;
+use lookup_tables::{
+    MediaTypeChars,
+    HttpToken, Token, QTextWs, CText
+};
+use char_class::CharClassTable;
+
+/// the implementation used by a `ClassScanner` to scan the input
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Backend {
+    /// one table lookup per byte
+    Scalar,
+    /// 16 bytes at a time using SSSE3 (`pshufb`), x86/x86_64 only
+    Ssse3,
+    /// 32 bytes at a time using AVX2, x86/x86_64 only
+    Avx2
+}
+
+impl Backend {
+
+    /// returns the fastest backend available on the current cpu
+    pub fn detect() -> Self {
+        if Backend::Avx2.is_available() {
+            Backend::Avx2
+        } else if Backend::Ssse3.is_available() {
+            Backend::Ssse3
+        } else {
+            Backend::Scalar
+        }
+    }
+
+    /// returns true if the backend can be used on the current cpu
+    pub fn is_available(self) -> bool {
+        match self {
+            Backend::Scalar => true,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Ssse3 => is_x86_feature_detected!("ssse3"),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            Backend::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+            _ => false
+        }
+    }
+}
+
+/// how a class treats bytes > 0x7f
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+enum NonAscii {
+    /// no byte > 0x7f is in the class
+    None,
+    /// all bytes > 0x7f are in the class
+    All,
+    /// some are in the class some are not, only the scalar backend can be used
+    Mixed
+}
+
+/// Finds the end of runs of chars of a given class, processing multiple bytes at a time if possible.
+///
+/// Scanning for the end of a token, qtext or ctext run byte by byte (i.e. using
+/// `MediaTypeChars::check_at` in a loop) is fairly slow on long inputs. This scanner uses
+/// SIMD instructions (AVX2 or SSSE3 on x86/x86_64, detected at runtime) and falls back to
+/// the per-byte lookup if they are not available. All backends return the same results.
+///
+/// Note that plain SSE2 is not enough, as the class lookup needs a byte shuffle (`pshufb`),
+/// so x86 cpus without SSSE3 as well as all other architectures (e.g. aarch64) use the
+/// per-byte lookup, there is no word-at-a-time (SWAR) fallback.
+///
+/// Creating a scanner requires a lookup for each of the 256 bytes so it should be reused,
+/// scanners for the most common classes are available through `find_first_not_http_token`,
+/// `find_first_not_token`, `find_first_not_qtext_ws` and `find_first_not_ctext`.
+///
+/// SIMD is only used for classes which either contain all or no bytes > 0x7f, which is
+/// the case for all classes in `MediaTypeChars`.
+///
+/// # Example
+///
+/// ```
+/// use media_type_impl_utils::scan::ClassScanner;
+/// use media_type_impl_utils::lookup_tables::HttpToken;
+///
+/// let scanner = ClassScanner::new(HttpToken);
+/// assert_eq!(scanner.find_first_not(b"text/plain"), Some(4));
+/// assert_eq!(scanner.find_first_not(b"text"), None);
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ClassScanner {
+    table: CharClassTable,
+    /// bit `hi` of `bitmap[lo]` is set if `(hi << 4) | lo` is in the class (for us-ascii)
+    bitmap: [u8; 16],
+    non_ascii: NonAscii,
+    backend: Backend
+}
+
+impl ClassScanner {
+
+    /// create a scanner for the given `MediaTypeChars` class
+    pub fn new<A: Access<MediaTypeChars>>(class: A) -> Self {
+        ClassScanner::from_table(CharClassTable::from_class(class))
+    }
+
+    /// create a scanner for a user defined class
+    pub fn from_table(table: CharClassTable) -> Self {
+        let mut bitmap = [0u8; 16];
+        for bch in 0..0x80u8 {
+            if table.contains(bch) {
+                bitmap[(bch & 0x0F) as usize] |= 1 << (bch >> 4);
+            }
+        }
+        let non_ascii_count = (0x80..=0xFFu8).filter(|&bch| table.contains(bch)).count();
+        let non_ascii = match non_ascii_count {
+            0 => NonAscii::None,
+            0x80 => NonAscii::All,
+            _ => NonAscii::Mixed
+        };
+        let backend =
+            if non_ascii == NonAscii::Mixed { Backend::Scalar } else { Backend::detect() };
+        ClassScanner { table, bitmap, non_ascii, backend }
+    }
+
+    /// the backend used by `find_first_not`
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    /// returns the index of the first byte which is not in the class
+    ///
+    /// Returns `None` if all bytes are in the class, i.e. the whole input is one run.
+    pub fn find_first_not(&self, input: &[u8]) -> Option<usize> {
+        self.find_first_not_using(self.backend, input)
+    }
+
+    /// like `find_first_not` but with a specific backend
+    ///
+    /// If the backend is not available on the current cpu, or can not be used
+    /// with the scanners class, the scalar backend is used.
+    pub fn find_first_not_using(&self, backend: Backend, input: &[u8]) -> Option<usize> {
+        if self.non_ascii == NonAscii::Mixed || !backend.is_available() {
+            return self.scalar(input, 0);
+        }
+        match backend {
+            Backend::Scalar => self.scalar(input, 0),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            // SAFE: the availability of the cpu features was checked above
+            Backend::Ssse3 => unsafe { self.ssse3(input) },
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            // SAFE: the availability of the cpu features was checked above
+            Backend::Avx2 => unsafe { self.avx2(input) },
+            #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+            _ => self.scalar(input, 0)
+        }
+    }
+
+    fn scalar(&self, input: &[u8], offset: usize) -> Option<usize> {
+        input[offset..].iter()
+            .position(|&bch| !self.table.contains(bch))
+            .map(|idx| idx + offset)
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[target_feature(enable = "ssse3")]
+    unsafe fn ssse3(&self, input: &[u8]) -> Option<usize> {
+        #[cfg(target_arch = "x86")]
+        use std::arch::x86::*;
+        #[cfg(target_arch = "x86_64")]
+        use std::arch::x86_64::*;
+
+        let bitmap = _mm_loadu_si128(self.bitmap.as_ptr() as *const __m128i);
+        let bits = _mm_setr_epi8(1, 2, 4, 8, 16, 32, 64, -128, 0, 0, 0, 0, 0, 0, 0, 0);
+        let low_nibble = _mm_set1_epi8(0x0F);
+        let zero = _mm_setzero_si128();
+        let all_non_ascii = self.non_ascii == NonAscii::All;
+
+        let mut offset = 0;
+        while offset + 16 <= input.len() {
+            let chunk = _mm_loadu_si128(input[offset..].as_ptr() as *const __m128i);
+            let lo = _mm_and_si128(chunk, low_nibble);
+            let hi = _mm_and_si128(_mm_srli_epi16(chunk, 4), low_nibble);
+            // bytes > 0x7f have a hi nibble >= 8, for which `bits` is 0
+            let in_class = _mm_and_si128(_mm_shuffle_epi8(bitmap, lo), _mm_shuffle_epi8(bits, hi));
+            let mut not_in_class = _mm_movemask_epi8(_mm_cmpeq_epi8(in_class, zero)) as u32;
+            if all_non_ascii {
+                not_in_class &= !(_mm_movemask_epi8(chunk) as u32);
+            }
+            if not_in_class != 0 {
+                return Some(offset + not_in_class.trailing_zeros() as usize);
+            }
+            offset += 16;
+        }
+        self.scalar(input, offset)
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[target_feature(enable = "avx2")]
+    unsafe fn avx2(&self, input: &[u8]) -> Option<usize> {
+        #[cfg(target_arch = "x86")]
+        use std::arch::x86::*;
+        #[cfg(target_arch = "x86_64")]
+        use std::arch::x86_64::*;
+
+        let bitmap = _mm256_broadcastsi128_si256(
+            _mm_loadu_si128(self.bitmap.as_ptr() as *const __m128i));
+        let bits = _mm256_setr_epi8(
+            1, 2, 4, 8, 16, 32, 64, -128, 0, 0, 0, 0, 0, 0, 0, 0,
+            1, 2, 4, 8, 16, 32, 64, -128, 0, 0, 0, 0, 0, 0, 0, 0);
+        let low_nibble = _mm256_set1_epi8(0x0F);
+        let zero = _mm256_setzero_si256();
+        let all_non_ascii = self.non_ascii == NonAscii::All;
+
+        let mut offset = 0;
+        while offset + 32 <= input.len() {
+            let chunk = _mm256_loadu_si256(input[offset..].as_ptr() as *const __m256i);
+            let lo = _mm256_and_si256(chunk, low_nibble);
+            let hi = _mm256_and_si256(_mm256_srli_epi16(chunk, 4), low_nibble);
+            // bytes > 0x7f have a hi nibble >= 8, for which `bits` is 0
+            let in_class = _mm256_and_si256(
+                _mm256_shuffle_epi8(bitmap, lo), _mm256_shuffle_epi8(bits, hi));
+            let mut not_in_class = _mm256_movemask_epi8(_mm256_cmpeq_epi8(in_class, zero)) as u32;
+            if all_non_ascii {
+                not_in_class &= !(_mm256_movemask_epi8(chunk) as u32);
+            }
+            if not_in_class != 0 {
+                return Some(offset + not_in_class.trailing_zeros() as usize);
+            }
+            offset += 32;
+        }
+        if offset + 16 <= input.len() {
+            return self.ssse3(&input[offset..]).map(|idx| idx + offset);
+        }
+        self.scalar(input, offset)
+    }
+}
+
+/// returns the index of the first byte which is not in the class
+///
+/// This creates a new `ClassScanner` on every call, so if the same class is used
+/// repeatedly the scanner should be created once and reused instead.
+pub fn find_first_not<A: Access<MediaTypeChars>>(class: A, input: &[u8]) -> Option<usize> {
+    ClassScanner::new(class).find_first_not(input)
+}
+
+macro_rules! def_shared_scanner {
+    ($(#[$attr:meta])* pub fn $name:ident = $class:expr;) => (
+        $(#[$attr])*
+        pub fn $name(input: &[u8]) -> Option<usize> {
+            static SCANNER: OnceLock<ClassScanner> = OnceLock::new();
+            SCANNER.get_or_init(|| ClassScanner::new($class)).find_first_not(input)
+        }
+    );
+}
+
+def_shared_scanner! {
+    /// returns the index of the first byte which is not a `HttpToken` char
+    pub fn find_first_not_http_token = HttpToken;
+}
+
+def_shared_scanner! {
+    /// returns the index of the first byte which is not a `Token` char
+    pub fn find_first_not_token = Token;
+}
+
+def_shared_scanner! {
+    /// returns the index of the first byte which is not a `QTextWs` char
+    pub fn find_first_not_qtext_ws = QTextWs;
+}
+
+def_shared_scanner! {
+    /// returns the index of the first byte which is not a `CText` char
+    pub fn find_first_not_ctext = CText;
+}
+
+#[cfg(test)]
+mod test {
+    use lut::Any;
+    use lookup_tables::{
+        HttpToken, Token, RestrictedToken, QTextWs, ObsQTextWs, CText, Ws, VCharWs
+    };
+    use char_class::CharClassTable;
+    use super::*;
+
+    const BACKENDS: &[Backend] = &[Backend::Scalar, Backend::Ssse3, Backend::Avx2];
+
+    /// simple deterministic pseudo random bytes
+    fn pseudo_random_input(seed: u32, len: usize, alphabet: &[u8]) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(2654435761).wrapping_add(1);
+        (0..len).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            alphabet[state as usize % alphabet.len()]
+        }).collect()
+    }
+
+    fn assert_all_backends_agree(scanner: &ClassScanner, input: &[u8]) {
+        let expected = input.iter().position(|&bch| !scanner.table.contains(bch));
+        for &backend in BACKENDS {
+            assert_eq!(scanner.find_first_not_using(backend, input), expected,
+                       "{:?} {:?}", backend, input);
+        }
+    }
+
+    fn check_class(scanner: ClassScanner) {
+        let members = (0..=255u8).filter(|&bch| scanner.table.contains(bch)).collect::<Vec<_>>();
+        let all = (0..=255u8).collect::<Vec<_>>();
+        for len in 0..100 {
+            // runs ending at any position
+            let mut input = pseudo_random_input(len as u32, len, &members);
+            assert_all_backends_agree(&scanner, &input);
+            for stop in 0..len {
+                let old = input[stop];
+                input[stop] = all[(stop * 7 + len) % all.len()];
+                assert_all_backends_agree(&scanner, &input);
+                input[stop] = old;
+            }
+        }
+        // any single byte at every position in a block
+        for bch in 0..=255u8 {
+            for pos in 0..40 {
+                let mut input = pseudo_random_input(pos as u32, 40, &members);
+                input[pos] = bch;
+                assert_all_backends_agree(&scanner, &input);
+            }
+        }
+    }
+
+    #[test]
+    fn all_backends_agree_for_tokens() {
+        check_class(ClassScanner::new(HttpToken));
+        check_class(ClassScanner::new(Token));
+        check_class(ClassScanner::new(RestrictedToken));
+    }
+
+    #[test]
+    fn all_backends_agree_for_qtext_and_ctext() {
+        check_class(ClassScanner::new(QTextWs));
+        check_class(ClassScanner::new(ObsQTextWs));
+        check_class(ClassScanner::new(CText));
+        check_class(ClassScanner::new(Any::new(CText) | Ws));
+        check_class(ClassScanner::new(VCharWs));
+    }
+
+    #[test]
+    fn all_backends_agree_for_classes_with_non_ascii() {
+        let utf8_qtext = CharClassTable::from_class(QTextWs)
+            .union(CharClassTable::from_fn(|bch| bch > 0x7f));
+        let scanner = ClassScanner::from_table(utf8_qtext);
+        assert_eq!(scanner.non_ascii, NonAscii::All);
+        check_class(scanner);
+
+        let mixed = CharClassTable::from_class(QTextWs)
+            .union(CharClassTable::from_bytes(b"\x80\xff"));
+        let scanner = ClassScanner::from_table(mixed);
+        assert_eq!(scanner.backend(), Backend::Scalar);
+        check_class(scanner);
+    }
+
+    #[test]
+    fn shared_scanners() {
+        assert_eq!(find_first_not_http_token(b"text/plain"), Some(4));
+        assert_eq!(find_first_not_token(b"x-{a}; q"), Some(5));
+        assert_eq!(find_first_not_qtext_ws(b"abc def\"x"), Some(7));
+        assert_eq!(find_first_not_ctext(b"a comment)"), Some(1));
+        assert_eq!(find_first_not(Any::new(CText) | Ws, b"a comment)"), Some(9));
+        assert_eq!(find_first_not_http_token(b""), None);
+    }
+}
@@ -0,0 +1,649 @@
+use std::error::{Error as StdError};
+use std::fmt::{self, Display};
+
+use lut::{Table, Access};
+use lookup_tables::MediaTypeChars;
+use qs::spec::{
+    PartialCodePoint,
+    WithoutQuotingValidator,
+};
+
+/// the kind of a `ValidationFailure`
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum FailureKind {
+    /// the char was rejected by a wrapped validator or char predicate
+    InvalidChar,
+    /// the first char did not match the predicate of `First`
+    InvalidFirstChar,
+    /// the last char did not match the predicate of `Last`
+    InvalidLastChar,
+    /// the input is shorter than the minimum of `MinLen`
+    TooShort {
+        /// the minimal length
+        min: usize
+    },
+    /// the input is longer than the maximum of `MaxLen`
+    TooLong {
+        /// the maximal length
+        max: usize
+    },
+    /// two consecutive chars matched the predicate of `NoConsecutive`
+    Consecutive,
+    /// a wrapped validator did not accept the end of the input
+    Incomplete
+}
+
+/// the reason a validator failed
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ValidationFailure {
+    /// the (byte) index the failure occurred at, for failures at the end of the
+    /// input (e.g. `TooShort`) this is the length of the input
+    pub index: usize,
+    /// what failed
+    pub kind: FailureKind
+}
+
+impl ValidationFailure {
+    fn new(index: usize, kind: FailureKind) -> Self {
+        ValidationFailure { index, kind }
+    }
+
+    fn shifted(self, by: usize) -> Self {
+        ValidationFailure { index: self.index + by, kind: self.kind }
+    }
+}
+
+impl Display for ValidationFailure {
+    fn fmt(&self, fter: &mut fmt::Formatter) -> fmt::Result {
+        use self::FailureKind::*;
+        match self.kind {
+            InvalidChar => write!(fter, "invalid char at {}", self.index),
+            InvalidFirstChar => write!(fter, "invalid first char at {}", self.index),
+            InvalidLastChar => write!(fter, "invalid last char at {}", self.index),
+            TooShort { min } => write!(fter, "input is shorter than {} chars", min),
+            TooLong { max } => write!(fter, "input is longer than {} chars", max),
+            Consecutive => write!(fter, "invalid consecutive char at {}", self.index),
+            Incomplete => write!(fter, "input ended unexpectedly at {}", self.index)
+        }
+    }
+}
+
+impl StdError for ValidationFailure {}
+
+/// a `WithoutQuotingValidator` which can tell why it failed
+pub trait ExplainFailure: WithoutQuotingValidator {
+    /// the reason for the failure of the last `next` call, or if it did not fail
+    /// the reason why `end` returns false, or `None` if neither failed
+    fn failure(&self) -> Option<ValidationFailure>;
+}
+
+/// a predicate for chars, implemented for closures (`Fn(u8) -> bool`) and `InClass`
+pub trait CharPredicate {
+    /// returns true if the char matches
+    fn matches(&self, bch: u8) -> bool;
+}
+
+impl<F> CharPredicate for F
+    where F: Fn(u8) -> bool
+{
+    fn matches(&self, bch: u8) -> bool {
+        (self)(bch)
+    }
+}
+
+/// a `CharPredicate` matching chars of a `MediaTypeChars` class (or accessor)
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct InClass<A>(pub A);
+
+impl<A> CharPredicate for InClass<A>
+    where A: Access<MediaTypeChars>
+{
+    fn matches(&self, bch: u8) -> bool {
+        self.0.check(MediaTypeChars::lookup(bch as usize))
+    }
+}
+
+/// a validator accepting any (possible empty) sequence of chars matching the predicate
+///
+/// created with `chars`
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct Chars<P> {
+    pred: P,
+    len: usize,
+    rejected: Option<ValidationFailure>
+}
+
+/// create a validator accepting any (possible empty) sequence of chars matching the predicate
+///
+/// # Example
+///
+/// ```
+/// use media_type_impl_utils::validator::{chars, InClass, ValidatorExt};
+/// use media_type_impl_utils::lookup_tables::RestrictedToken;
+///
+/// // the same as `StrictTokenValidator`
+/// let strict_token = chars(InClass(RestrictedToken))
+///     .first(|bch: u8| bch.is_ascii_alphanumeric())
+///     .max_len(127);
+/// ```
+pub fn chars<P: CharPredicate>(pred: P) -> Chars<P> {
+    Chars { pred, len: 0, rejected: None }
+}
+
+impl<P> WithoutQuotingValidator for Chars<P>
+    where P: CharPredicate
+{
+    fn next(&mut self, pcp: PartialCodePoint) -> bool {
+        if self.pred.matches(pcp.as_u8()) {
+            self.len += 1;
+            self.rejected = None;
+            true
+        } else {
+            self.rejected = Some(ValidationFailure::new(self.len, FailureKind::InvalidChar));
+            false
+        }
+    }
+}
+
+impl<P> ExplainFailure for Chars<P>
+    where P: CharPredicate
+{
+    fn failure(&self) -> Option<ValidationFailure> {
+        self.rejected
+    }
+}
+
+/// wraps any `WithoutQuotingValidator` so that it can be used with the combinators
+///
+/// created with `explained`, its failures are `InvalidChar` and `Incomplete`
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct Explained<V> {
+    inner: V,
+    len: usize,
+    rejected: Option<ValidationFailure>
+}
+
+/// wraps any `WithoutQuotingValidator` so that it can be used with the combinators
+pub fn explained<V: WithoutQuotingValidator>(inner: V) -> Explained<V> {
+    Explained { inner, len: 0, rejected: None }
+}
+
+impl<V> WithoutQuotingValidator for Explained<V>
+    where V: WithoutQuotingValidator
+{
+    fn next(&mut self, pcp: PartialCodePoint) -> bool {
+        if self.inner.next(pcp) {
+            self.len += 1;
+            self.rejected = None;
+            true
+        } else {
+            self.rejected = Some(ValidationFailure::new(self.len, FailureKind::InvalidChar));
+            false
+        }
+    }
+
+    fn end(&self) -> bool {
+        self.inner.end()
+    }
+}
+
+impl<V> ExplainFailure for Explained<V>
+    where V: WithoutQuotingValidator
+{
+    fn failure(&self) -> Option<ValidationFailure> {
+        self.rejected.or_else(|| {
+            if self.inner.end() {
+                None
+            } else {
+                Some(ValidationFailure::new(self.len, FailureKind::Incomplete))
+            }
+        })
+    }
+}
+
+/// defines a combinator wrapping a inner validator with a additional check
+///
+/// `$check` is called before the char is passed to the inner validator and
+/// returns the failure kind if the char is rejected, `$accepted` is called after
+/// the char was accepted by both, `$end` checks the end condition
+macro_rules! def_combinator {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident<V $(, $P:ident)*> {
+            $($field:ident: $fty:ty),*
+        }
+        fn check(&$cs:ident, $cbch:ident) -> Option<FailureKind> $check:block
+        fn accepted(&mut $as_:ident, $abch:ident) $accepted:block
+        fn end(&$es:ident) -> Option<ValidationFailure> $end:block
+    ) => (
+        $(#[$meta])*
+        #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+        pub struct $name<V $(, $P)*> {
+            inner: V,
+            len: usize,
+            rejected: Option<ValidationFailure>,
+            $($field: $fty),*
+        }
+
+        impl<V $(, $P)*> $name<V $(, $P)*>
+            where V: ExplainFailure $(, $P: CharPredicate)*
+        {
+            #[allow(unused_variables)]
+            fn check_char(&$cs, $cbch: u8) -> Option<FailureKind> $check
+
+            #[allow(unused_variables)]
+            fn char_accepted(&mut $as_, $abch: u8) $accepted
+
+            fn end_failure(&$es) -> Option<ValidationFailure> $end
+        }
+
+        impl<V $(, $P)*> WithoutQuotingValidator for $name<V $(, $P)*>
+            where V: ExplainFailure $(, $P: CharPredicate)*
+        {
+            fn next(&mut self, pcp: PartialCodePoint) -> bool {
+                let bch = pcp.as_u8();
+                if let Some(kind) = self.check_char(bch) {
+                    self.rejected = Some(ValidationFailure::new(self.len, kind));
+                    return false;
+                }
+                if !self.inner.next(pcp) {
+                    self.rejected = self.inner.failure();
+                    return false;
+                }
+                self.char_accepted(bch);
+                self.len += 1;
+                self.rejected = None;
+                true
+            }
+
+            fn end(&self) -> bool {
+                self.inner.end() && self.end_failure().is_none()
+            }
+        }
+
+        impl<V $(, $P)*> ExplainFailure for $name<V $(, $P)*>
+            where V: ExplainFailure $(, $P: CharPredicate)*
+        {
+            fn failure(&self) -> Option<ValidationFailure> {
+                self.rejected
+                    .or_else(|| self.inner.failure())
+                    .or_else(|| self.end_failure())
+            }
+        }
+    );
+}
+
+def_combinator! {
+    /// requires the input to be at least `min` chars long, created with `ValidatorExt::min_len`
+    pub struct MinLen<V> {
+        min: usize
+    }
+    fn check(&self, bch) -> Option<FailureKind> { None }
+    fn accepted(&mut self, bch) {}
+    fn end(&self) -> Option<ValidationFailure> {
+        if self.len < self.min {
+            Some(ValidationFailure::new(self.len, FailureKind::TooShort { min: self.min }))
+        } else {
+            None
+        }
+    }
+}
+
+def_combinator! {
+    /// rejects any char after the first `max` chars, created with `ValidatorExt::max_len`
+    pub struct MaxLen<V> {
+        max: usize
+    }
+    fn check(&self, bch) -> Option<FailureKind> {
+        if self.len >= self.max {
+            Some(FailureKind::TooLong { max: self.max })
+        } else {
+            None
+        }
+    }
+    fn accepted(&mut self, bch) {}
+    fn end(&self) -> Option<ValidationFailure> { None }
+}
+
+def_combinator! {
+    /// requires the first char to match the predicate, created with `ValidatorExt::first`
+    pub struct First<V, P> {
+        pred: P
+    }
+    fn check(&self, bch) -> Option<FailureKind> {
+        if self.len == 0 && !self.pred.matches(bch) {
+            Some(FailureKind::InvalidFirstChar)
+        } else {
+            None
+        }
+    }
+    fn accepted(&mut self, bch) {}
+    fn end(&self) -> Option<ValidationFailure> { None }
+}
+
+def_combinator! {
+    /// requires the last char (if any) to match the predicate, created with `ValidatorExt::last`
+    pub struct Last<V, P> {
+        pred: P,
+        last_matches: bool
+    }
+    fn check(&self, bch) -> Option<FailureKind> { None }
+    fn accepted(&mut self, bch) {
+        self.last_matches = self.pred.matches(bch);
+    }
+    fn end(&self) -> Option<ValidationFailure> {
+        if self.len > 0 && !self.last_matches {
+            Some(ValidationFailure::new(self.len - 1, FailureKind::InvalidLastChar))
+        } else {
+            None
+        }
+    }
+}
+
+def_combinator! {
+    /// rejects a char matching the predicate directly after a char matching it,
+    /// created with `ValidatorExt::no_consecutive`
+    pub struct NoConsecutive<V, P> {
+        pred: P,
+        last_matches: bool
+    }
+    fn check(&self, bch) -> Option<FailureKind> {
+        if self.last_matches && self.pred.matches(bch) {
+            Some(FailureKind::Consecutive)
+        } else {
+            None
+        }
+    }
+    fn accepted(&mut self, bch) {
+        self.last_matches = self.pred.matches(bch);
+    }
+    fn end(&self) -> Option<ValidationFailure> { None }
+}
+
+/// the sequence of two validators, created with `ValidatorExt::then`
+///
+/// Chars are passed to the first validator until it rejects one, if it accepts the
+/// input up to this point (`end`) the rest is passed to the second validator. This is
+/// greedy, i.e. the first validator gets as much of the input as it accepts.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct Then<A, B> {
+    first: A,
+    second: B,
+    first_len: usize,
+    in_second: bool
+}
+
+impl<A, B> WithoutQuotingValidator for Then<A, B>
+    where A: ExplainFailure, B: ExplainFailure
+{
+    fn next(&mut self, pcp: PartialCodePoint) -> bool {
+        if self.in_second {
+            return self.second.next(pcp);
+        }
+        if self.first.next(pcp) {
+            self.first_len += 1;
+            true
+        } else if self.first.end() && self.second.next(pcp) {
+            self.in_second = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn end(&self) -> bool {
+        (self.in_second || self.first.end()) && self.second.end()
+    }
+}
+
+impl<A, B> ExplainFailure for Then<A, B>
+    where A: ExplainFailure, B: ExplainFailure
+{
+    fn failure(&self) -> Option<ValidationFailure> {
+        if self.in_second {
+            return self.second.failure().map(|failure| failure.shifted(self.first_len));
+        }
+        // if the first did end fine the failure is in the second
+        if self.first.end() {
+            self.second.failure().map(|failure| failure.shifted(self.first_len))
+        } else {
+            self.first.failure()
+        }
+    }
+}
+
+/// the alternation of two validators, created with `ValidatorExt::or`
+///
+/// Chars are passed to both validators, it accepts the input if either of them does.
+/// If both fail the failure of the one which failed later is returned.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
+pub struct Or<A, B> {
+    left: A,
+    right: B,
+    left_failed: bool,
+    right_failed: bool
+}
+
+impl<A, B> WithoutQuotingValidator for Or<A, B>
+    where A: ExplainFailure, B: ExplainFailure
+{
+    fn next(&mut self, pcp: PartialCodePoint) -> bool {
+        let left_ok = !self.left_failed && self.left.next(pcp);
+        let right_ok = !self.right_failed && self.right.next(pcp);
+        if left_ok || right_ok {
+            self.left_failed |= !left_ok;
+            self.right_failed |= !right_ok;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn end(&self) -> bool {
+        (!self.left_failed && self.left.end()) || (!self.right_failed && self.right.end())
+    }
+}
+
+impl<A, B> ExplainFailure for Or<A, B>
+    where A: ExplainFailure, B: ExplainFailure
+{
+    fn failure(&self) -> Option<ValidationFailure> {
+        match (self.left.failure(), self.right.failure()) {
+            (Some(left), Some(right)) => {
+                // `end` might be fine even if the last `next` call failed
+                if right.index > left.index { Some(right) } else { Some(left) }
+            },
+            _ => None
+        }
+    }
+}
+
+/// extension trait providing the combinators for any `ExplainFailure` validator
+///
+/// To use them with a `WithoutQuotingValidator` not implementing `ExplainFailure`
+/// wrap it with `explained`.
+///
+/// # Example
+///
+/// ```
+/// extern crate quoted_string;
+/// extern crate media_type_impl_utils;
+///
+/// use quoted_string::spec::{PartialCodePoint, WithoutQuotingValidator};
+/// use media_type_impl_utils::validator::{
+///     chars, InClass, ValidatorExt, ExplainFailure, FailureKind
+/// };
+/// use media_type_impl_utils::lookup_tables::{BChars, BCharsNoSpace};
+///
+/// fn main() {
+///     // the same rules as `validate_boundary`
+///     let mut vali = chars(InClass(BChars))
+///         .min_len(1)
+///         .max_len(70)
+///         .last(InClass(BCharsNoSpace));
+///
+///     for bch in b"boundary ".iter() {
+///         assert!(vali.next(PartialCodePoint::from_utf8_byte(*bch)));
+///     }
+///     assert!(!vali.end());
+///     assert_eq!(vali.failure().unwrap().kind, FailureKind::InvalidLastChar);
+/// }
+/// ```
+pub trait ValidatorExt: ExplainFailure + Sized {
+
+    /// require the input to be at least `min` chars long
+    fn min_len(self, min: usize) -> MinLen<Self> {
+        MinLen { inner: self, len: 0, rejected: None, min }
+    }
+
+    /// reject any char after the first `max` chars
+    fn max_len(self, max: usize) -> MaxLen<Self> {
+        MaxLen { inner: self, len: 0, rejected: None, max }
+    }
+
+    /// require the first char to match the predicate
+    fn first<P: CharPredicate>(self, pred: P) -> First<Self, P> {
+        First { inner: self, len: 0, rejected: None, pred }
+    }
+
+    /// require the last char (if there is any) to match the predicate
+    fn last<P: CharPredicate>(self, pred: P) -> Last<Self, P> {
+        Last { inner: self, len: 0, rejected: None, pred, last_matches: false }
+    }
+
+    /// reject a char matching the predicate directly after a char matching it
+    fn no_consecutive<P: CharPredicate>(self, pred: P) -> NoConsecutive<Self, P> {
+        NoConsecutive { inner: self, len: 0, rejected: None, pred, last_matches: false }
+    }
+
+    /// continue with the other validator once this one rejects a char
+    fn then<B: ExplainFailure>(self, second: B) -> Then<Self, B> {
+        Then { first: self, second, first_len: 0, in_second: false }
+    }
+
+    /// accept the input if this or the other validator accepts it
+    fn or<B: ExplainFailure>(self, right: B) -> Or<Self, B> {
+        Or { left: self, right, left_failed: false, right_failed: false }
+    }
+}
+
+impl<V> ValidatorExt for V
+    where V: ExplainFailure
+{}
+
+#[cfg(test)]
+mod test {
+    use qs::spec::{WithoutQuotingValidator, PartialCodePoint};
+    use lookup_tables::{
+        RestrictedToken, BChars, BCharsNoSpace, SfKey, SfKeyStart
+    };
+    use quoted_string::{
+        StrictTokenValidator, validate_boundary, SfKeyValidator, HttpTokenValidator
+    };
+    use super::*;
+
+    /// validates the input like the quoted-string crate does (stopping at the first rejected char)
+    fn validate<V: ExplainFailure>(mut vali: V, input: &str) -> Result<(), ValidationFailure> {
+        let all_accepted = input.bytes().all(|bch| vali.next(PartialCodePoint::from_utf8_byte(bch)));
+        if all_accepted && vali.end() {
+            assert_eq!(vali.failure(), None);
+            Ok(())
+        } else {
+            Err(vali.failure().expect("failed validator has a failure reason"))
+        }
+    }
+
+    fn is_valid<V: WithoutQuotingValidator>(mut vali: V, input: &str) -> bool {
+        input.bytes().all(|bch| vali.next(PartialCodePoint::from_utf8_byte(bch))) && vali.end()
+    }
+
+    fn failure(index: usize, kind: FailureKind) -> Result<(), ValidationFailure> {
+        Err(ValidationFailure { index, kind })
+    }
+
+    const INPUTS: &[&str] = &[
+        "", "a", "abc", "a b", "a ", " a", "1abc", "-abc", "a.b+c", "a..b", "a=b?c", "*a",
+        "a*", "aB", "a\"b", "x-abc_d.e",
+        "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+        "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaab",
+    ];
+
+    #[test]
+    fn strict_token_validator_as_combinators() {
+        let make = || chars(InClass(RestrictedToken))
+            .first(|bch: u8| bch.is_ascii_alphanumeric())
+            .max_len(127);
+        for input in INPUTS {
+            assert_eq!(is_valid(make(), input), is_valid(StrictTokenValidator::default(), input),
+                       "{:?}", input);
+        }
+        assert_eq!(validate(make(), "-a"), failure(0, FailureKind::InvalidFirstChar));
+        assert_eq!(validate(make(), &"a".repeat(128)), failure(127, FailureKind::TooLong { max: 127 }));
+    }
+
+    #[test]
+    fn multipart_boundary_validator_as_combinators() {
+        let make = || chars(InClass(BChars)).min_len(1).max_len(70).last(InClass(BCharsNoSpace));
+        for input in INPUTS {
+            assert_eq!(is_valid(make(), input), validate_boundary(input).is_ok(), "{:?}", input);
+        }
+        assert_eq!(validate(make(), ""), failure(0, FailureKind::TooShort { min: 1 }));
+        assert_eq!(validate(make(), "a "), failure(1, FailureKind::InvalidLastChar));
+        assert_eq!(validate(make(), "a;"), failure(1, FailureKind::InvalidChar));
+    }
+
+    #[test]
+    fn sf_key_validator_as_combinators() {
+        let make = || chars(InClass(SfKey)).first(InClass(SfKeyStart));
+        for input in INPUTS {
+            assert_eq!(is_valid(make(), input), is_valid(SfKeyValidator::new(), input), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn no_consecutive() {
+        let make = || chars(|bch: u8| bch.is_ascii_alphanumeric() || bch == b'.')
+            .no_consecutive(|bch: u8| bch == b'.');
+        assert_eq!(validate(make(), "a.b.c"), Ok(()));
+        assert_eq!(validate(make(), "a..b"), failure(2, FailureKind::Consecutive));
+    }
+
+    #[test]
+    fn then() {
+        let make = || chars(|bch: u8| bch.is_ascii_alphabetic()).min_len(1)
+            .then(chars(|bch: u8| bch.is_ascii_digit()).min_len(1));
+        assert_eq!(validate(make(), "abc123"), Ok(()));
+        assert_eq!(validate(make(), "abc"), failure(3, FailureKind::TooShort { min: 1 }));
+        assert_eq!(validate(make(), "123"), failure(0, FailureKind::InvalidChar));
+        assert_eq!(validate(make(), "ab1c"), failure(3, FailureKind::InvalidChar));
+        assert_eq!(validate(make(), "ab-1"), failure(2, FailureKind::InvalidChar));
+    }
+
+    #[test]
+    fn or() {
+        let make = || chars(|bch: u8| bch.is_ascii_digit()).min_len(2)
+            .or(chars(|bch: u8| bch.is_ascii_alphabetic()).max_len(3));
+        assert_eq!(validate(make(), "12"), Ok(()));
+        assert_eq!(validate(make(), "abc"), Ok(()));
+        assert_eq!(validate(make(), "1"), failure(1, FailureKind::TooShort { min: 2 }));
+        assert_eq!(validate(make(), "abcd"), failure(3, FailureKind::TooLong { max: 3 }));
+        assert_eq!(validate(make(), "12a"), failure(2, FailureKind::InvalidChar));
+    }
+
+    #[test]
+    fn explained_wraps_existing_validators() {
+        let make = || explained(HttpTokenValidator::new()).max_len(3);
+        assert_eq!(validate(make(), "abc"), Ok(()));
+        assert_eq!(validate(make(), "a b"), failure(1, FailureKind::InvalidChar));
+        assert_eq!(validate(make(), "abcd"), failure(3, FailureKind::TooLong { max: 3 }));
+    }
+
+    #[test]
+    fn rejected_next_does_not_change_end() {
+        let mut vali = chars(|bch: u8| bch == b'a').min_len(1);
+        assert!(vali.next(PartialCodePoint::from_utf8_byte(b'a')));
+        assert!(!vali.next(PartialCodePoint::from_utf8_byte(b'b')));
+        assert!(vali.end());
+        assert_eq!(vali.failure(), Some(ValidationFailure { index: 1, kind: FailureKind::InvalidChar }));
+    }
+}